@@ -41,26 +41,40 @@ let openDict = function (evt, dictName) {
 }
 
 let start_time = Date.now();
+let rendered = false;
 
-fetch('/api/define/' + word).then(function (response) {
-    return response.json();
-}).then(function (data) {
-    console.log('fetched in ' + (Date.now() - start_time) / 1000 + 's');
-
-    console.log(data);
-
+let render = function (data) {
     let word_container = $('#word')[0];
+    word_container.innerHTML = '';
 
-    if (Object.keys(data).length === 0) {
+    if (data['error']) {
         let div = createEl('div');
         appendEl(div, "h3", { inner: "couldn't find that word", clazz: 'gray', style: 'padding: 25% 0' });
-        appendEl(div, 'p', { inner: 'Try removing endings such as -ed or -s,<br>or try changing the capitilazation' });
-        appendEl(div, 'p', { inner: 'Phrases might be harder to find, and there\'s no autocorrect yet' });
+        let suggestions = data['suggestions'] || [];
+        if (suggestions.length !== 0) {
+            let links = suggestions.map(function (word) {
+                return '<a href="/define/' + encodeURIComponent(word) + '">' + word + '</a>';
+            });
+            appendEl(div, 'p', { inner: 'Did you mean ' + links.join(', ') + '?' });
+        } else {
+            appendEl(div, 'p', { inner: 'Check the spelling and try again' });
+        }
         word_container.appendChild(div);
         word_container.className += 'fade-in';
         return;
     }
 
+    // the sources file the word under a different spelling
+    if (data['redirect']) {
+        $('h1')[0].innerText = data['headword'];
+        let from = data['redirect']['from'];
+        let note = {
+            inflection: from + ' → ' + data['headword'],
+            redirect: 'redirected from <i>' + from + '</i>',
+        }[data['redirect']['how']] || 'you searched for <i>' + from + '</i>';
+        appendEl(word_container, 'p', { clazz: 'gray', inner: note });
+    }
+
     let word_left = createEl('div', { clazz: 'word-column' });
     let word_right = createEl('div', { clazz: 'word-column' });
 
@@ -79,19 +93,16 @@ fetch('/api/define/' + word).then(function (response) {
     let tab_div = createEl('div', { clazz: 'tab-bar' });
 
     let first_sel = ' bar-selected';
-    let add_tab = function (title, id, data) {
-        if (!data || data.length === 0)
-            return;
+    let add_tab = function (title, id) {
         appendEl(tab_div, 'button', { clazz: 'bar-item' + first_sel, inner: title, onclick: `openDict(event, '${id}')` });
         if (first_sel) {
             first_sel = '';
         }
     }
 
-    add_tab('Macmillan', 'Macmillan', data['macmillan_defs']);
-    add_tab('Vocabulary.com', 'Vocab', data['vocab_defs']);
-    add_tab('Wikitionary', 'Wiki', data['wiki_defs']);
-    add_tab('Urban Dictionary', 'Urban', data['urban_defs']);
+    for (let dict of data['dictionaries']) {
+        add_tab(dict['name'], dict['source']);
+    }
 
     word_left.appendChild(tab_div);
 
@@ -141,20 +152,16 @@ fetch('/api/define/' + word).then(function (response) {
                 return li;
             };
 
-            if (Array.isArray(def)) {
-                let first = appendSense(def[0], 'numbered');
+            let first = appendSense(def[0], 'numbered');
+            if (def.length > 1) {
                 let subsenses = createEl('ol');
-                def.shift()
-                for (let subsense of def) {
+                for (let subsense of def.slice(1)) {
                     let sub_li = appendSense(subsense, 'lettered');
                     subsenses.appendChild(sub_li);
                 }
                 first.appendChild(subsenses);
-                entries[entries.length - 1].appendChild(first);
-            } else {
-                let first = appendSense(def, 'numbered');
-                entries[entries.length - 1].appendChild(first);
             }
+            entries[entries.length - 1].appendChild(first);
         }
 
         for (entry of entries) {
@@ -164,19 +171,18 @@ fetch('/api/define/' + word).then(function (response) {
         word_left.appendChild(defs_div);
     }
 
-    add_definitions('Macmillan', data['macmillan_defs']);
-    add_definitions('Vocab', data['vocab_defs']);
-    add_definitions('Wiki', data['wiki_defs']);
-    add_definitions('Urban', data['urban_defs']);
+    for (let dict of data['dictionaries']) {
+        add_definitions(dict['source'], dict['definitions']);
+    }
 
     // now the right side
 
-    if (data['stock_images']) {
+    if (data['media'].length !== 0) {
         let img_container = createEl('div', { clazz: 'img-container' });
-        for (idx in data['stock_images']) {
+        for (let media of data['media']) {
             appendEl(img_container, 'img', {
                 clazz: 'stock-img',
-                source: data['stock_images'][idx]
+                source: media
             });
         }
         word_right.appendChild(img_container);
@@ -207,20 +213,16 @@ fetch('/api/define/' + word).then(function (response) {
         }
     }
 
-    if (data['etym_origins']) {
-        appendEl(origin_div, 'h2', { inner: 'Word Origin' });
-        add_origins(data['etym_origins']);
-    } else if (data['wiki_origins']) {
+    if (data['origins'].length !== 0) {
         appendEl(origin_div, 'h2', { inner: 'Word Origin' });
-        add_origins(data['wiki_origins']);
+        add_origins(data['origins']);
     }
 
     word_right.appendChild(origin_div);
 
     let sources = createEl('div', { clazz: 'sources' });
     appendEl(sources, 'h4', { clazz: 'fancy', inner: 'Not the right word?' })
-    appendEl(sources, 'p', { inner: 'Try removing endings such as -ed or -s,<br>or try changing the capitilazation' });
-    appendEl(sources, 'p', { inner: 'Phrases might be harder to find, and there\'s no autocorrect yet' });
+    appendEl(sources, 'p', { inner: 'Check the spelling, or look it up under another form' });
     appendEl(sources, 'br');
     appendEl(sources, 'h4', { clazz: 'fancy', inner: 'Sources' })
     for (let source of data['sources']) {
@@ -233,9 +235,69 @@ fetch('/api/define/' + word).then(function (response) {
     word_container.appendChild(word_left);
     word_container.appendChild(word_right);
 
-    word_container.className += 'slide-up';
+    if (!rendered) {
+        word_container.className += 'slide-up';
+        rendered = true;
+    }
+}
+
+let finish = function (data) {
+    console.log('fetched in ' + (Date.now() - start_time) / 1000 + 's');
+    console.log(data);
+    render(data);
+}
+
+let fetchWhole = function () {
+    fetch('/api/define/' + encodeURIComponent(word)).then(function (response) {
+        return response.json();
+    }).then(finish).catch(function (err) {
+        console.log('Fetch Error :-S', err);
+    });
+}
+
+// show each dictionary as soon as it's in, rather than waiting on the slowest
+let streamWord = function () {
+    let stream = new EventSource('/api/define/' + encodeURIComponent(word) + '/stream');
+    let finished = false;
+    let partial = { overview: [], dictionaries: [], origins: [], media: [], sources: [] };
+
+    stream.addEventListener('source', function (event) {
+        let report = JSON.parse(event.data);
+        if (report['outcome'] !== 'found')
+            return;
+
+        let result = report['detail'];
+        partial['overview'].push(...result['overview']);
+        if (result['definitions'].length !== 0) {
+            partial['dictionaries'].push({ source: report['source'], name: report['name'], definitions: result['definitions'] });
+        }
+        if (partial['origins'].length === 0) {
+            partial['origins'] = result['origins'];
+        }
+        partial['media'].push(...result['media']);
+        partial['sources'].push(result['attribution']);
+
+        if (partial['dictionaries'].length !== 0)
+            render(partial);
+    });
+
+    let end = function (event) {
+        finished = true;
+        stream.close();
+        finish(JSON.parse(event.data));
+    }
+    stream.addEventListener('word', end);
+    stream.addEventListener('not_found', end);
+
+    stream.onerror = function () {
+        stream.close();
+        if (!finished)
+            fetchWhole();
+    }
+}
 
-    console.log('finished in ' + (Date.now() - start_time) / 1000 + 's');
-}).catch(function (err) {
-    console.log('Fetch Error :-S', err);
-});
\ No newline at end of file
+if (window.EventSource) {
+    streamWord();
+} else {
+    fetchWhole();
+}
\ No newline at end of file
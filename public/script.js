@@ -5,10 +5,40 @@ function search(ele) {
         let new_word = ele.value;
         ele.value = '';
         ele.blur();
-        window.location.href = '/define/' + new_word;
+        window.location.href = '/define/' + encodeURIComponent(new_word.trim());
     }
 }
 
+// the words typed so far, so answers that come back out of order are dropped
+let completing = '';
+
+function complete(ele) {
+    let prefix = ele.value.trimStart();
+    completing = prefix;
+    let list = document.getElementById('completions');
+    if (!list) {
+        return;
+    }
+    if (prefix === '') {
+        list.innerHTML = '';
+        return;
+    }
+    fetch('/api/suggest?q=' + encodeURIComponent(prefix))
+        .then(function (response) { return response.json(); })
+        .then(function (words) {
+            if (prefix !== completing) {
+                return;
+            }
+            list.innerHTML = '';
+            for (let word of words) {
+                let option = document.createElement('option');
+                option.value = word;
+                list.appendChild(option);
+            }
+        })
+        .catch(function () {});
+}
+
 document.onkeydown = function (e) {
     e = e || window.event;
     if (!e.ctrlKey && !e.metaKey && e.key.length === 1 && input !== document.activeElement) {
@@ -0,0 +1,29 @@
+#!/bin/sh
+# Captures the pages in tests/fixtures from the live sites, then rewrites the
+# goldens next to them from what the parsers make of the new pages. Review the
+# diff of the .json files before committing: a changed golden is either the
+# site adding or rewording something, or the parser missing something new.
+#
+#     scripts/fixtures.sh
+set -e
+cd "$(dirname "$0")/../tests/fixtures"
+
+agent="the-everything-dictionary/0.1.0"
+
+capture() {
+    echo "$1/$2.html <- $3" >&2
+    # the no-results pages can come back with a 404, which is still the page
+    curl -sSL --compressed -A "$agent" -o "$1/$2.html" "$3"
+}
+
+capture etym dog 'https://www.etymonline.com/word/dog'
+capture macmillan run 'https://www.macmillandictionary.com/us/dictionary/american/run'
+capture macmillan dgo 'https://www.macmillandictionary.com/us/dictionary/american/dgo'
+capture stock dog 'https://stock.adobe.com/search?k=dog'
+capture vocab dog 'https://www.vocabulary.com/dictionary/definition.ajax?search=dog&lang=en'
+capture vocab dgo 'https://www.vocabulary.com/dictionary/definition.ajax?search=dgo&lang=en'
+capture wiki dog 'https://en.wiktionary.org/wiki/dog'
+
+cd ../..
+UPDATE_GOLDEN=1 cargo test -q parses_saved_page
+cargo test -q sources
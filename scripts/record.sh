@@ -0,0 +1,34 @@
+#!/bin/sh
+# Records a real lookup of each word given (just "dog" if none are) into
+# tests/recordings, by running the site in record mode against the live
+# sources. The recordings keep every header and redirect the sites sent, so
+# the replay tests see what a real lookup does. Check `replays_recorded_lookup`
+# in src/dict/mod.rs against what the sources answer now before committing.
+#
+#     scripts/record.sh [word...]
+set -e
+cd "$(dirname "$0")/.."
+
+port=8765
+dir=$(mktemp -d)
+trap 'kill $server 2>/dev/null; rm -rf "$dir"' EXIT
+
+cargo build -q
+ROCKET_PORT=$port \
+ROCKET_HTTP="{mode=\"record\",dir=\"$dir\"}" \
+ROCKET_CANARY='{enabled=false}' \
+    target/debug/the-everything-dictionary >"$dir/server.log" 2>&1 &
+server=$!
+
+until curl -s -o /dev/null "http://localhost:$port/"; do
+    kill -0 $server 2>/dev/null || { cat "$dir/server.log" >&2; exit 1; }
+    sleep 1
+done
+
+[ $# -gt 0 ] || set -- dog
+for word in "$@"; do
+    echo "recording $word" >&2
+    curl -s -o /dev/null "http://localhost:$port/api/define/$word"
+done
+
+mv "$dir"/*.json tests/recordings/
@@ -0,0 +1,22 @@
+#!/bin/sh
+# Rebuilds words.txt from Peter Norvig's big.txt (https://norvig.com/big.txt):
+# every word that comes up at least three times in the books it's made of,
+# most common first. Project Gutenberg's headers, footers and licenses are
+# left out, along with the odd license word that shows up in a book itself.
+#
+#     scripts/words.sh big.txt > words.txt
+set -e
+export LC_ALL=C
+
+awk '
+    /^\*\*\* ?START OF/ || /^\*END THE SMALL PRINT/ { keep = 1; next }
+    /^\*\*\* ?END OF/ || /^End of (the )?Project Gutenberg/ || /^\*\*\*START\*\*THE SMALL PRINT/ { keep = 0 }
+    keep
+' "$1" |
+    tr 'A-Z' 'a-z' |
+    tr -cs 'a-z' '\n' |
+    grep -vxE '|[b-hj-z]|gutenberg|ebooks?|etext|tm|http|www|org|html?|txt|zip|pglaf' |
+    sort | uniq -c |
+    awk '$1 >= 3' |
+    sort -k1,1nr -k2,2 |
+    awk '{ print $2 }'
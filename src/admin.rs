@@ -0,0 +1,67 @@
+use rocket::{
+    fairing::AdHoc,
+    http::Status,
+    request::{FromRequest, Outcome},
+    Build, Request, Rocket,
+};
+use serde::Deserialize;
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub(crate) struct AdminConfig {
+    /// What admin requests send as `Authorization: Bearer <token>`. The admin
+    /// routes are off without one.
+    pub(crate) token: Option<String>,
+}
+
+/// A request that came with the admin token.
+pub(crate) struct Admin;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Admin {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let token = request
+            .rocket()
+            .state::<AdminConfig>()
+            .and_then(|config| config.token.as_deref())
+            .filter(|token| !token.is_empty());
+        // as far as anyone can tell, there's nothing here
+        let Some(token) = token else {
+            return Outcome::Forward(Status::NotFound);
+        };
+
+        let given = request
+            .headers()
+            .get_one("Authorization")
+            .and_then(|value| value.strip_prefix("Bearer "));
+        match given {
+            Some(given) if same(given.as_bytes(), token.as_bytes()) => Outcome::Success(Admin),
+            _ => Outcome::Error((Status::Unauthorized, ())),
+        }
+    }
+}
+
+/// Compares without stopping at the first difference, so the time it takes
+/// doesn't give away how much of a guess was right.
+fn same(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// Manages the `admin` config. Keep the token out of `Rocket.toml`, e.g.
+/// `ROCKET_ADMIN='{token="..."}'`.
+pub(crate) fn fairing() -> AdHoc {
+    AdHoc::try_on_ignite("Admin", |rocket: Rocket<Build>| async move {
+        let config: AdminConfig = match rocket.figment().extract_inner("admin") {
+            Ok(config) => config,
+            Err(err) if err.missing() => AdminConfig::default(),
+            Err(err) => {
+                error!("invalid admin config: {}", err);
+                return Err(rocket);
+            }
+        };
+
+        Ok(rocket.manage(config))
+    })
+}
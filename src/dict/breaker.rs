@@ -0,0 +1,199 @@
+use std::{sync::Mutex, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub(crate) struct BreakerConfig {
+    /// Failures in a row before a source is left alone.
+    pub(crate) failures: u32,
+    /// How long it's left alone before being tried again.
+    pub(crate) cooldown_secs: u64,
+}
+
+impl Default for BreakerConfig {
+    fn default() -> Self {
+        Self {
+            failures: 5,
+            cooldown_secs: 60,
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum BreakerState {
+    /// The source is asked as usual.
+    Closed,
+    /// The source kept failing and isn't being asked.
+    Open,
+    /// The cool-down is over and one lookup is trying the source again.
+    HalfOpen,
+}
+
+/// What the health endpoint shows for a breaker.
+#[derive(Serialize, Debug)]
+pub(crate) struct BreakerStatus {
+    pub(crate) state: BreakerState,
+    pub(crate) consecutive_failures: u32,
+    /// Until the source is tried again, while open.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) retry_in_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) last_error: Option<String>,
+}
+
+struct Inner {
+    state: BreakerState,
+    failures: u32,
+    /// When the breaker last opened or started probing.
+    changed: Instant,
+    last_error: Option<String>,
+}
+
+/// Stops asking a source that keeps failing, so lookups don't all pay for
+/// fetching a page that can't be parsed. After a cool-down one lookup gets
+/// to try it again: if that works the breaker closes, otherwise it waits
+/// out another cool-down.
+pub(crate) struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    inner: Mutex<Inner>,
+}
+
+impl CircuitBreaker {
+    pub(crate) fn new(config: &BreakerConfig) -> Self {
+        Self {
+            threshold: config.failures.max(1),
+            cooldown: Duration::from_secs(config.cooldown_secs),
+            inner: Mutex::new(Inner {
+                state: BreakerState::Closed,
+                failures: 0,
+                changed: Instant::now(),
+                last_error: None,
+            }),
+        }
+    }
+
+    /// Whether the source should be asked right now.
+    pub(crate) fn allow(&self) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.state {
+            BreakerState::Closed => true,
+            // a probe that never reported back (say, its lookup was
+            // dropped) shouldn't keep the source shut out forever
+            BreakerState::Open | BreakerState::HalfOpen
+                if inner.changed.elapsed() >= self.cooldown =>
+            {
+                inner.state = BreakerState::HalfOpen;
+                inner.changed = Instant::now();
+                true
+            }
+            BreakerState::Open | BreakerState::HalfOpen => false,
+        }
+    }
+
+    /// Records that the source answered, whether or not it had the word.
+    /// Returns whether that closed the breaker.
+    pub(crate) fn succeed(&self) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        let was_open = inner.state != BreakerState::Closed;
+        inner.state = BreakerState::Closed;
+        inner.failures = 0;
+        was_open
+    }
+
+    /// Records that the source couldn't be fetched or parsed. Returns
+    /// whether that opened the breaker.
+    pub(crate) fn fail(&self, error: String) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        inner.failures = inner.failures.saturating_add(1);
+        inner.last_error = Some(error);
+
+        let opens = match inner.state {
+            BreakerState::Closed => inner.failures >= self.threshold,
+            BreakerState::HalfOpen => true,
+            BreakerState::Open => false,
+        };
+        if opens {
+            inner.state = BreakerState::Open;
+            inner.changed = Instant::now();
+        }
+        opens
+    }
+
+    pub(crate) fn status(&self) -> BreakerStatus {
+        let inner = self.inner.lock().unwrap();
+        BreakerStatus {
+            state: inner.state,
+            consecutive_failures: inner.failures,
+            retry_in_secs: (inner.state == BreakerState::Open).then(|| {
+                self.cooldown
+                    .saturating_sub(inner.changed.elapsed())
+                    .as_secs()
+            }),
+            last_error: inner.last_error.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn breaker(cooldown_ms: u64) -> CircuitBreaker {
+        let mut breaker = CircuitBreaker::new(&BreakerConfig {
+            failures: 3,
+            cooldown_secs: 0,
+        });
+        breaker.cooldown = Duration::from_millis(cooldown_ms);
+        breaker
+    }
+
+    #[test]
+    fn opens_after_failures_in_a_row() {
+        let breaker = breaker(60_000);
+
+        assert!(!breaker.fail("oops".to_string()));
+        assert!(!breaker.fail("oops".to_string()));
+        breaker.succeed();
+        assert!(!breaker.fail("oops".to_string()));
+        assert!(!breaker.fail("oops".to_string()));
+        assert!(breaker.allow());
+
+        assert!(breaker.fail("still oops".to_string()));
+        assert!(!breaker.allow());
+
+        let status = breaker.status();
+        assert_eq!(status.state, BreakerState::Open);
+        assert_eq!(status.consecutive_failures, 3);
+        assert_eq!(status.last_error.as_deref(), Some("still oops"));
+        assert!(status.retry_in_secs.is_some());
+    }
+
+    #[rocket::async_test]
+    async fn probes_once_after_the_cooldown() {
+        let breaker = breaker(50);
+        for _ in 0..3 {
+            breaker.fail("oops".to_string());
+        }
+        assert!(!breaker.allow());
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(breaker.allow());
+        assert_eq!(breaker.status().state, BreakerState::HalfOpen);
+        // only the one probe
+        assert!(!breaker.allow());
+
+        // a failed probe opens it straight away
+        assert!(breaker.fail("oops".to_string()));
+        assert!(!breaker.allow());
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(breaker.allow());
+        assert!(breaker.succeed());
+        assert_eq!(breaker.status().state, BreakerState::Closed);
+        assert!(breaker.allow());
+    }
+}
@@ -0,0 +1,1010 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use rocket::{
+    fairing::AdHoc,
+    futures::{
+        future::{join_all, BoxFuture},
+        StreamExt,
+    },
+    Build, Orbit, Rocket,
+};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    sync::watch,
+    time::{timeout_at, Instant},
+};
+
+use super::{
+    lemma, scrape::HeadwordMatch, sources::SourceRegistry, spelling::Speller, storage::Storage,
+    Outcome, Redirect, SourceReport, Word,
+};
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub(crate) struct CacheConfig {
+    /// How old an entry can get before it's scraped again.
+    pub(crate) max_age_secs: u64,
+    /// How long a word, or a source, is remembered not to have a word.
+    /// Shorter than `max_age_secs`, since dictionaries add words.
+    pub(crate) miss_max_age_secs: u64,
+    /// How often misses that have expired are deleted.
+    pub(crate) sweep_secs: u64,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            max_age_secs: 60 * 60 * 24 * 7,
+            miss_max_age_secs: 60 * 60 * 24,
+            sweep_secs: 60 * 60,
+        }
+    }
+}
+
+/// A remembered "doesn't have it", from one source or from all of them.
+#[derive(Serialize, Deserialize)]
+struct Miss {
+    reason: String,
+    /// In milliseconds since the epoch.
+    at: u64,
+}
+
+/// How a scrape in progress is getting on.
+#[derive(Default)]
+pub(crate) struct Flight {
+    /// Every source that has answered so far, in the order they did.
+    pub(crate) reports: Vec<SourceReport>,
+    /// Whether every source has answered.
+    pub(crate) done: bool,
+    /// What the lookup came to, once it's done.
+    pub(crate) word: Option<Word>,
+    /// The stale entry being scraped again, if there is one.
+    pub(crate) stale: Option<Word>,
+}
+
+/// Where a lookup's answer is coming from.
+pub(crate) enum Following {
+    /// A fresh entry was in the cache.
+    Cached(Box<Word>),
+    /// The word is being scraped. Lookups of the same word follow the same
+    /// scrape, which carries on even if they all go away.
+    Scraping(watch::Receiver<Flight>),
+    /// No source had the word when it was last looked up.
+    Missing,
+}
+
+/// Scraped words, stored as json and keyed by their normalized headword.
+#[derive(Clone)]
+pub(crate) struct WordCache {
+    storage: Arc<dyn Storage>,
+    sources: Arc<SourceRegistry>,
+    max_age: Duration,
+    miss_max_age: Duration,
+    /// Scrapes in progress, by normalized word, so a word everyone is
+    /// looking up at once is only scraped once.
+    in_flight: Arc<Mutex<HashMap<String, watch::Receiver<Flight>>>>,
+    /// Knows every word in the cache, for suggesting them.
+    speller: Arc<Speller>,
+}
+
+impl WordCache {
+    pub(crate) fn new(
+        storage: Arc<dyn Storage>,
+        sources: Arc<SourceRegistry>,
+        config: &CacheConfig,
+    ) -> Self {
+        Self {
+            storage,
+            sources,
+            max_age: Duration::from_secs(config.max_age_secs),
+            miss_max_age: Duration::from_secs(config.miss_max_age_secs),
+            in_flight: Arc::default(),
+            speller: Arc::default(),
+        }
+    }
+
+    pub(crate) fn fairing() -> AdHoc {
+        AdHoc::try_on_ignite("Word Cache", |rocket: Rocket<Build>| async move {
+            let config: CacheConfig = match rocket.figment().extract_inner("cache") {
+                Ok(config) => config,
+                Err(err) if err.missing() => CacheConfig::default(),
+                Err(err) => {
+                    error!("invalid cache config: {}", err);
+                    return Err(rocket);
+                }
+            };
+
+            let Some(storage) = rocket.state::<Arc<dyn Storage>>().cloned() else {
+                error!("the word cache needs storage to be attached first");
+                return Err(rocket);
+            };
+
+            let Some(sources) = rocket.state::<Arc<SourceRegistry>>().cloned() else {
+                error!("the word cache needs sources to be attached first");
+                return Err(rocket);
+            };
+
+            let sweep = Duration::from_secs(config.sweep_secs.max(1));
+
+            let cache = WordCache::new(storage, sources, &config);
+            cache.learn_cached().await;
+            Ok(rocket
+                .manage(cache)
+                .attach(AdHoc::on_liftoff("Miss Sweeper", move |rocket| {
+                    Box::pin(async move { WordCache::keep_tidy(rocket, sweep) })
+                })))
+        })
+    }
+
+    fn keep_tidy(rocket: &Rocket<Orbit>, sweep: Duration) {
+        let cache = rocket.state::<WordCache>().unwrap().clone();
+        let mut shutdown = rocket.shutdown();
+
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = &mut shutdown => break,
+                    _ = tokio::time::sleep(sweep) => {},
+                }
+
+                let swept = cache.sweep_misses().await;
+                if swept > 0 {
+                    info!("forgot {} expired misses", swept);
+                }
+            }
+        });
+    }
+
+    /// Returns the cached entry for `word` along with whether it has gone stale.
+    pub(crate) async fn get(&self, word: &str) -> Option<(Word, bool)> {
+        let key = key(word);
+
+        let json = match self.storage.get(&key).await {
+            Ok(json) => json?,
+            Err(err) => {
+                warn!("couldn't read `{}` from the cache: {}", word, err);
+                return None;
+            }
+        };
+
+        // entries written by an older version of `Word` won't parse, which
+        // is the same as not having them at all
+        let Ok(entry) = serde_json::from_str::<Word>(&json) else {
+            let _ = self.storage.delete(&key).await;
+            return None;
+        };
+        let stale = entry.age().is_none_or(|age| age > self.max_age);
+
+        Some((entry, stale))
+    }
+
+    pub(crate) async fn insert(&self, word: &str, entry: &Word) {
+        let json = serde_json::to_string(entry).expect("Word should always serialize");
+
+        if let Err(err) = self.storage.set(&key(word), &json).await {
+            warn!("couldn't cache `{}`: {}", word, err);
+        }
+        self.speller.learn(&normalize(word));
+        self.speller.learn(&entry.headword);
+    }
+
+    /// Every word with an entry in the cache, fresh or not.
+    pub(crate) async fn cached_words(&self) -> Vec<String> {
+        match self.storage.scan("word:").await {
+            Ok(entries) => entries
+                .into_iter()
+                .filter_map(|(key, _)| Some(key.strip_prefix("word:")?.to_string()))
+                .collect(),
+            Err(err) => {
+                warn!("couldn't read the cached words: {}", err);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Teaches the speller every word already in the cache.
+    async fn learn_cached(&self) {
+        for word in self.cached_words().await {
+            self.speller.learn(&word);
+        }
+    }
+
+    /// What `word` might have been meant to be, out of the word list and
+    /// every word in the cache.
+    pub(crate) fn suggest(&self, word: &str) -> Vec<String> {
+        self.speller.suggest(&normalize(word))
+    }
+
+    /// The remembered miss at `key`, unless it has expired.
+    async fn get_miss(&self, key: &str) -> Option<Miss> {
+        let json = match self.storage.get(key).await {
+            Ok(json) => json?,
+            Err(err) => {
+                warn!("couldn't read `{}` from the cache: {}", key, err);
+                return None;
+            }
+        };
+
+        let miss = self.unexpired(&json);
+        if miss.is_none() {
+            let _ = self.storage.delete(key).await;
+        }
+        miss
+    }
+
+    /// The miss stored as `json`, unless it has expired or can't be read.
+    fn unexpired(&self, json: &str) -> Option<Miss> {
+        serde_json::from_str::<Miss>(json).ok().filter(|miss| {
+            Duration::from_millis(now().saturating_sub(miss.at)) <= self.miss_max_age
+        })
+    }
+
+    /// Deletes every miss that has expired, returning how many there were.
+    /// Reading one deletes it too, but most misses are typos and garbage
+    /// nobody asks for twice, which would otherwise be kept forever.
+    pub(crate) async fn sweep_misses(&self) -> usize {
+        let mut swept = 0;
+        for prefix in ["miss:", "miss-from:"] {
+            let misses = match self.storage.scan(prefix).await {
+                Ok(misses) => misses,
+                Err(err) => {
+                    warn!("couldn't read the cached misses: {}", err);
+                    continue;
+                }
+            };
+            for (key, json) in misses {
+                if self.unexpired(&json).is_some() {
+                    continue;
+                }
+                match self.storage.delete(&key).await {
+                    Ok(()) => swept += 1,
+                    Err(err) => warn!("couldn't forget `{}`: {}", key, err),
+                }
+            }
+        }
+        swept
+    }
+
+    async fn insert_miss(&self, key: &str, reason: &str) {
+        let miss = Miss {
+            reason: reason.to_string(),
+            at: now(),
+        };
+        let json = serde_json::to_string(&miss).expect("Miss should always serialize");
+
+        if let Err(err) = self.storage.set(key, &json).await {
+            warn!("couldn't cache `{}`: {}", key, err);
+        }
+    }
+
+    /// Forgets that `word` wasn't found, by any source or by all of them, so
+    /// the next lookup asks everywhere again. Returns whether there was
+    /// anything to forget.
+    pub(crate) async fn forget_misses(&self, word: &str) -> bool {
+        let keys = self
+            .sources
+            .iter()
+            .map(|source| source_miss_key(source.id(), word))
+            .chain([miss_key(word)]);
+
+        let mut forgot = false;
+        for key in keys {
+            match self.storage.get(&key).await {
+                Ok(Some(_)) => forgot = true,
+                Ok(None) => continue,
+                Err(err) => warn!("couldn't read `{}` from the cache: {}", key, err),
+            }
+            if let Err(err) = self.storage.delete(&key).await {
+                warn!("couldn't forget `{}`: {}", key, err);
+            }
+        }
+        forgot
+    }
+
+    /// Serves `word` from the cache, scraping it again if it's missing or stale.
+    /// A stale entry is still returned when the sources have nothing better.
+    /// Concurrent lookups of the same word share one scrape.
+    ///
+    /// A scrape that's still going after the registry's `respond_within`
+    /// finishes into the cache for the next lookup. In the meantime a stale
+    /// entry is answered with, marked as refreshing, or else whatever the
+    /// sources that are done have, as long as one of them found the word.
+    pub(crate) async fn lookup(&self, word: &str) -> Option<Word> {
+        let mut flight = match self.follow(word).await {
+            Following::Cached(entry) => return Some(*entry),
+            Following::Scraping(flight) => flight,
+            Following::Missing => return None,
+        };
+
+        let respond_by = Instant::now() + self.sources.respond_within;
+        if let Ok(done) = timeout_at(respond_by, flight.wait_for(|flight| flight.done)).await {
+            return done.ok()?.word.clone();
+        }
+
+        let (reports, stale) = {
+            let flight = flight.borrow();
+            (flight.reports.clone(), flight.stale.clone())
+        };
+        if let Some(mut stale) = stale {
+            stale.refreshing = true;
+            return Some(stale);
+        }
+        if let Some(partial) = Word::assemble(&normalize(word), reports, &self.sources) {
+            return Some(partial);
+        }
+
+        // nothing found yet isn't the same as not found, so wait it out
+        let flight = flight.wait_for(|flight| flight.done).await.ok()?;
+        flight.word.clone()
+    }
+
+    /// Finds `word` in the cache, or else joins or starts a scrape of it.
+    pub(crate) async fn follow(&self, word: &str) -> Following {
+        self.follow_or_start(word, true).await
+    }
+
+    /// Like `follow`, but a scrape it starts only looks up `word`'s base
+    /// forms when `fall_back` is set.
+    async fn follow_or_start(&self, word: &str, fall_back: bool) -> Following {
+        let stale = match self.get(word).await {
+            Some((entry, false)) => return Following::Cached(Box::new(entry)),
+            Some((entry, true)) => Some(entry),
+            None => None,
+        };
+        if stale.is_none() && self.get_miss(&miss_key(word)).await.is_some() {
+            return Following::Missing;
+        }
+
+        let normalized = normalize(word);
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if let Some(flight) = in_flight.get(&normalized) {
+            return Following::Scraping(flight.clone());
+        }
+
+        let (sender, flight) = watch::channel(Flight {
+            stale: stale.clone(),
+            ..Default::default()
+        });
+        in_flight.insert(normalized.clone(), flight.clone());
+        drop(in_flight);
+
+        tokio::spawn(self.clone().scrape(normalized, stale, fall_back, sender));
+        Following::Scraping(flight)
+    }
+
+    /// Asks every source about `word`, passing each report to `on_report` as
+    /// it comes in. Sources that recently didn't have the word aren't asked
+    /// again, and the ones that don't have it now are remembered.
+    async fn ask_sources(
+        &self,
+        word: &str,
+        mut on_report: impl FnMut(SourceReport),
+    ) -> Vec<SourceReport> {
+        let mut known_misses = HashMap::new();
+        for source in self.sources.iter() {
+            if let Some(miss) = self.get_miss(&source_miss_key(source.id(), word)).await {
+                known_misses.insert(source.id(), miss.reason);
+            }
+        }
+
+        let mut reports = Vec::new();
+        let mut scrape = Word::scrape_each(word, &self.sources, &known_misses);
+        while let Some(report) = scrape.next().await {
+            if let Outcome::Missing(reason) = &report.outcome {
+                if !known_misses.contains_key(report.source) {
+                    self.insert_miss(&source_miss_key(report.source, word), reason)
+                        .await;
+                }
+            }
+            reports.push(report.clone());
+            on_report(report);
+        }
+        reports
+    }
+
+    /// Looks up the first of `candidates` the sources know, for a `word`
+    /// that might be an inflection of it, and gives its entry as `word`'s.
+    /// The candidates are asked about all at once, so trying a few doesn't
+    /// take a few times as long.
+    async fn look_up_base_form(&self, word: &str, candidates: Vec<String>) -> Option<Word> {
+        let candidates: Vec<_> = candidates.into_iter().take(lemma::MAX_TRIES).collect();
+        let found = join_all(
+            candidates
+                .iter()
+                .map(|candidate| self.look_up_candidate(candidate)),
+        )
+        .await;
+        let (candidate, mut base) = candidates
+            .iter()
+            .zip(found)
+            .find_map(|(candidate, base)| Some((candidate, base?)))?;
+
+        info!("`{}` wasn't found, but `{}` was", word, candidate);
+        base.redirect = Some(Redirect {
+            from: word.to_string(),
+            how: HeadwordMatch::Inflection,
+        });
+        Some(base)
+    }
+
+    /// The entry for a base form `candidate`, from the cache or else the
+    /// sources. It's scraped like any other word, so a lookup of the word
+    /// itself at the same time shares the scrape, and a miss is remembered
+    /// so the same inflection doesn't send the sources looking again.
+    async fn look_up_candidate(&self, candidate: &str) -> Option<Word> {
+        let mut flight = match self.follow_or_start(candidate, false).await {
+            Following::Cached(base) => return Some(*base),
+            Following::Scraping(flight) => flight,
+            Following::Missing => return None,
+        };
+        let flight = flight.wait_for(|flight| flight.done).await.ok()?;
+        flight.word.clone()
+    }
+
+    fn scrape(
+        self,
+        word: String,
+        stale: Option<Word>,
+        fall_back: bool,
+        sender: watch::Sender<Flight>,
+    ) -> BoxFuture<'static, ()> {
+        // boxed, since a scrape can start scrapes of the word's base forms,
+        // and the compiler can't tell that a future that spawns more of
+        // itself is `Send` unless it's told
+        Box::pin(async move {
+            let landing = Landing {
+                in_flight: self.in_flight.clone(),
+                word: word.clone(),
+            };
+            let deadline = Instant::now() + self.sources.deadline;
+
+            let reports = self
+                .ask_sources(&word, |report| {
+                    sender.send_modify(|flight| flight.reports.push(report));
+                })
+                .await;
+
+            // only remember a miss when every source actually answered, rather
+            // than when some of them were down
+            let mut answered = reports.iter().all(|report| report.outcome.is_answer());
+            let mut entry = Word::assemble(&word, reports, &self.sources);
+
+            // "mice" might not be in every dictionary, but "mouse" will be
+            let (named, only_forms) = entry.as_ref().map_or((Vec::new(), true), |entry| {
+                lemma::named_base_forms(&word, entry)
+            });
+            if fall_back && only_forms {
+                let mut candidates = named;
+                for candidate in lemma::candidates(&word) {
+                    if !candidates.contains(&candidate) {
+                        candidates.push(candidate);
+                    }
+                }
+                // the base forms get whatever's left of the word's own deadline
+                match timeout_at(deadline, self.look_up_base_form(&word, candidates)).await {
+                    Ok(Some(base)) => entry = Some(base),
+                    Ok(None) => {}
+                    Err(_) => {
+                        info!("ran out of time looking up `{}`'s base forms", word);
+                        answered = false;
+                    }
+                }
+            }
+
+            let entry = match entry {
+                Some(entry) => {
+                    self.insert(&word, &entry).await;
+                    Some(entry)
+                }
+                None if stale.is_none() && answered => {
+                    self.insert_miss(&miss_key(&word), "no source has it").await;
+                    None
+                }
+                None => stale,
+            };
+
+            // the entry is in the cache by now, so later lookups can start there
+            drop(landing);
+            sender.send_modify(|flight| {
+                flight.done = true;
+                flight.word = entry;
+            });
+        })
+    }
+}
+
+/// Takes a scrape out of `in_flight` once it's over, however it ends. A
+/// scrape that panicked would otherwise stay there, and every later lookup
+/// of the word would follow it and never hear back.
+struct Landing {
+    in_flight: Arc<Mutex<HashMap<String, watch::Receiver<Flight>>>>,
+    word: String,
+}
+
+impl Drop for Landing {
+    fn drop(&mut self) {
+        if let Ok(mut in_flight) = self.in_flight.lock() {
+            in_flight.remove(&self.word);
+        }
+    }
+}
+
+fn key(word: &str) -> String {
+    format!("word:{}", normalize(word))
+}
+
+fn miss_key(word: &str) -> String {
+    format!("miss:{}", normalize(word))
+}
+
+fn source_miss_key(source: &str, word: &str) -> String {
+    format!("miss-from:{}:{}", source, normalize(word))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_millis() as u64
+}
+
+/// The cache key for a word. Case is kept because the sources treat
+/// "Polish" and "polish" as different words.
+pub(crate) fn normalize(word: &str) -> String {
+    word.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeMap,
+        sync::atomic::{AtomicUsize, Ordering},
+        time::{SystemTime, UNIX_EPOCH},
+    };
+
+    use reqwest::StatusCode;
+
+    use crate::dict::{
+        scrape::ScrapeError,
+        sources::{
+            fixtures::{self, defined_as, Stub},
+            DictionarySource, ScrapeConfig, Vocabulary,
+        },
+        storage::MemoryStorage,
+    };
+
+    use super::*;
+
+    fn entry(last_updated: Duration) -> Word {
+        Word {
+            headword: "dog".to_string(),
+            redirect: None,
+            overview: vec!["a domesticated canid".to_string()],
+            dictionaries: Vec::new(),
+            origins: Vec::new(),
+            media: Vec::new(),
+            sources: Vec::new(),
+            errors: BTreeMap::new(),
+            timed_out: Vec::new(),
+            pending: Vec::new(),
+            refreshing: false,
+            last_updated: last_updated.as_millis().to_string(),
+            version_0_2_0: String::new(),
+        }
+    }
+
+    fn now() -> Duration {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap()
+    }
+
+    fn cache() -> WordCache {
+        WordCache::new(
+            Arc::new(MemoryStorage::default()),
+            Arc::default(),
+            &CacheConfig::default(),
+        )
+    }
+
+    #[rocket::async_test]
+    async fn entries_are_served_until_they_go_stale() {
+        let cache = cache();
+        assert!(cache.get("dog").await.is_none());
+
+        cache.insert("dog", &entry(now())).await;
+        let (word, stale) = cache.get("dog").await.unwrap();
+        assert_eq!(word.overview, ["a domesticated canid"]);
+        assert!(!stale);
+
+        let eight_days = Duration::from_secs(60 * 60 * 24 * 8);
+        cache.insert("dog", &entry(now() - eight_days)).await;
+        let (_, stale) = cache.get("dog").await.unwrap();
+        assert!(stale);
+    }
+
+    #[rocket::async_test]
+    async fn keys_are_normalized() {
+        let cache = cache();
+        cache.insert("  kick the\tbucket ", &entry(now())).await;
+
+        assert!(cache.get("kick the bucket").await.is_some());
+        assert!(cache.get("Kick the bucket").await.is_none());
+    }
+
+    /// Knows every word, after `delay_ms`.
+    fn slow(id: &'static str, delay_ms: u64) -> Arc<Stub> {
+        Arc::new(Stub::new(id, |word| Ok(defined_as(format!("a {}", word)))).delayed(delay_ms))
+    }
+
+    #[rocket::async_test]
+    async fn concurrent_lookups_share_a_scrape() {
+        let slow = slow("slow", 50);
+        let sources =
+            SourceRegistry::new(vec![slow.clone()], Arc::default(), &ScrapeConfig::default());
+        let cache = WordCache::new(
+            Arc::new(MemoryStorage::default()),
+            Arc::new(sources),
+            &CacheConfig::default(),
+        );
+
+        let lookups = join_all((0..5).map(|_| cache.lookup("dog"))).await;
+        assert!(lookups.iter().all(Option::is_some));
+        assert_eq!(slow.scrapes(), 1);
+
+        // words that normalize the same share too, other words get their own
+        join_all([
+            cache.lookup("cat"),
+            cache.lookup(" cat "),
+            cache.lookup("emu"),
+        ])
+        .await;
+        assert_eq!(slow.scrapes(), 3);
+
+        // and once it's done, it's in the cache
+        cache.lookup("dog").await.unwrap();
+        assert_eq!(slow.scrapes(), 3);
+    }
+
+    #[rocket::async_test]
+    async fn a_panicking_scrape_is_not_followed_forever() {
+        let broken = Arc::new(Stub::new("broken", |word| {
+            if word == "dog" {
+                panic!("the parser tripped over something");
+            }
+            Ok(defined_as(format!("a {}", word)))
+        }));
+        let sources = SourceRegistry::new(
+            vec![broken.clone()],
+            Arc::default(),
+            &ScrapeConfig::default(),
+        );
+        let cache = WordCache::new(
+            Arc::new(MemoryStorage::default()),
+            Arc::new(sources),
+            &CacheConfig::default(),
+        );
+
+        assert!(cache.lookup("dog").await.is_none());
+        assert!(cache.in_flight.lock().unwrap().is_empty());
+
+        // the next lookup tries again rather than joining the dead scrape
+        assert!(cache.lookup("dog").await.is_none());
+        assert_eq!(broken.scrapes(), 2);
+        assert!(cache.lookup("cat").await.is_some());
+    }
+
+    #[rocket::async_test]
+    async fn slow_sources_finish_in_the_background() {
+        let (fast, slow) = (slow("fast", 0), slow("slow", 200));
+        let sources = SourceRegistry::new(
+            vec![fast.clone(), slow.clone()],
+            Arc::default(),
+            &ScrapeConfig {
+                respond_ms: 50,
+                ..Default::default()
+            },
+        );
+        let cache = WordCache::new(
+            Arc::new(MemoryStorage::default()),
+            Arc::new(sources),
+            &CacheConfig::default(),
+        );
+
+        let partial = cache.lookup("dog").await.unwrap();
+        let found: Vec<_> = partial
+            .dictionaries
+            .iter()
+            .map(|dict| &dict.source)
+            .collect();
+        assert_eq!(found, ["fast"]);
+        assert_eq!(partial.pending, ["slow"]);
+        // the partial answer isn't cached
+        assert!(cache.get("dog").await.is_none());
+
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        let whole = cache.lookup("dog").await.unwrap();
+        let found: Vec<_> = whole.dictionaries.iter().map(|dict| &dict.source).collect();
+        assert_eq!(found, ["fast", "slow"]);
+        assert!(whole.pending.is_empty());
+        assert_eq!(fast.scrapes() + slow.scrapes(), 2);
+    }
+
+    #[rocket::async_test]
+    async fn stale_entries_are_answered_while_they_refresh() {
+        let (fast, slow) = (slow("fast", 0), slow("slow", 200));
+        let sources = SourceRegistry::new(
+            vec![fast, slow],
+            Arc::default(),
+            &ScrapeConfig {
+                respond_ms: 50,
+                ..Default::default()
+            },
+        );
+        let cache = WordCache::new(
+            Arc::new(MemoryStorage::default()),
+            Arc::new(sources),
+            &CacheConfig::default(),
+        );
+        let eight_days = Duration::from_secs(60 * 60 * 24 * 8);
+        cache.insert("dog", &entry(now() - eight_days)).await;
+
+        // the old entry has everything, where "fast" alone would only have
+        // some of it
+        let stale = cache.lookup("dog").await.unwrap();
+        assert_eq!(stale.overview, ["a domesticated canid"]);
+        assert!(stale.refreshing);
+        assert!(stale.pending.is_empty());
+
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        let fresh = cache.lookup("dog").await.unwrap();
+        assert_eq!(fresh.dictionaries.len(), 2);
+        assert!(!fresh.refreshing);
+    }
+
+    /// Doesn't have any words.
+    fn nowhere() -> Arc<Stub> {
+        Arc::new(Stub::new("nowhere", |_| {
+            Err(ScrapeError::Status(StatusCode::NOT_FOUND))
+        }))
+    }
+
+    #[rocket::async_test]
+    async fn misses_are_remembered_until_forgotten() {
+        let nowhere = nowhere();
+        let storage = Arc::new(MemoryStorage::default());
+        let cache = |sources: Vec<Arc<dyn DictionarySource>>| {
+            WordCache::new(
+                storage.clone(),
+                Arc::new(SourceRegistry::new(
+                    sources,
+                    Arc::default(),
+                    &ScrapeConfig::default(),
+                )),
+                &CacheConfig {
+                    max_age_secs: 0,
+                    ..Default::default()
+                },
+            )
+        };
+
+        // no source has it, so nobody is asked again
+        let nothing = cache(vec![nowhere.clone()]);
+        assert!(nothing.lookup("dgo").await.is_none());
+        assert!(nothing.lookup("dgo").await.is_none());
+        assert_eq!(nowhere.scrapes(), 1);
+
+        assert!(nothing.forget_misses("dgo").await);
+        assert!(!nothing.forget_misses("dgo").await);
+        assert!(nothing.lookup("dgo").await.is_none());
+        assert_eq!(nowhere.scrapes(), 2);
+
+        // one source has it, so only the other one is skipped next time
+        let fast = slow("fast", 0);
+        let some = cache(vec![fast.clone(), nowhere.clone()]);
+        let word = some.lookup("dog").await.unwrap();
+        assert!(word.errors.contains_key("nowhere"));
+        tokio::time::sleep(Duration::from_millis(5)).await;
+
+        let word = some.lookup("dog").await.unwrap();
+        assert!(word.errors.contains_key("nowhere"));
+        assert_eq!(fast.scrapes(), 2);
+        assert_eq!(nowhere.scrapes(), 3);
+    }
+
+    #[rocket::async_test]
+    async fn a_no_results_page_is_remembered_as_a_miss() {
+        // answers a typo with its "did you mean" page rather than a 404
+        let vocab = Arc::new(Stub::new("vocab", |word| {
+            Vocabulary.parse(word, &fixtures::page(&Vocabulary, "dgo"))
+        }));
+        let nowhere = nowhere();
+        let sources = SourceRegistry::new(
+            vec![vocab.clone(), nowhere.clone()],
+            Arc::default(),
+            &ScrapeConfig::default(),
+        );
+        let cache = WordCache::new(
+            Arc::new(MemoryStorage::default()),
+            Arc::new(sources),
+            &CacheConfig::default(),
+        );
+
+        assert!(cache.lookup("dgo").await.is_none());
+        assert!(cache.get_miss(&miss_key("dgo")).await.is_some());
+
+        assert!(cache.lookup("dgo").await.is_none());
+        assert_eq!(vocab.scrapes() + nowhere.scrapes(), 2);
+    }
+
+    #[rocket::async_test]
+    async fn expired_misses_are_swept() {
+        let storage = Arc::new(MemoryStorage::default());
+        let cache = WordCache::new(storage.clone(), Arc::default(), &CacheConfig::default());
+
+        cache
+            .insert_miss(&miss_key("dgo"), "no source has it")
+            .await;
+        cache
+            .insert_miss(&source_miss_key("vocab", "dgo"), "not found")
+            .await;
+        cache
+            .insert_miss(&miss_key("teh"), "no source has it")
+            .await;
+        // nobody looked these up again in the two days since
+        let two_days_ago = super::now() - 2 * 24 * 60 * 60 * 1000;
+        for key in [miss_key("dgo"), source_miss_key("vocab", "dgo")] {
+            let json = format!(r#"{{"reason":"gone","at":{}}}"#, two_days_ago);
+            storage.set(&key, &json).await.unwrap();
+        }
+
+        assert_eq!(cache.sweep_misses().await, 2);
+        assert_eq!(storage.get(&miss_key("dgo")).await.unwrap(), None);
+        assert_eq!(
+            storage.get(&source_miss_key("vocab", "dgo")).await.unwrap(),
+            None
+        );
+        assert!(cache.get_miss(&miss_key("teh")).await.is_some());
+        assert_eq!(cache.sweep_misses().await, 0);
+    }
+
+    /// Knows a few words, some of them only as forms of others.
+    fn lexicon() -> Arc<Stub> {
+        Arc::new(Stub::new("lexicon", |word| match word {
+            "run" => Ok(defined_as("To move quickly on foot.")),
+            "mouse" => Ok(defined_as("A small rodent.")),
+            "mice" => Ok(defined_as("plural of <i>mouse</i>")),
+            _ => Err(ScrapeError::Status(StatusCode::NOT_FOUND)),
+        }))
+    }
+
+    #[rocket::async_test]
+    async fn inflections_fall_back_to_their_base_form() {
+        let sources =
+            SourceRegistry::new(vec![lexicon()], Arc::default(), &ScrapeConfig::default());
+        let cache = WordCache::new(
+            Arc::new(MemoryStorage::default()),
+            Arc::new(sources),
+            &CacheConfig::default(),
+        );
+
+        let word = cache.lookup("running").await.unwrap();
+        assert_eq!(word.headword, "run");
+        let redirect = word.redirect.unwrap();
+        assert_eq!(redirect.from, "running");
+        assert_eq!(redirect.how, HeadwordMatch::Inflection);
+        // both are cached now
+        assert!(cache.get("running").await.is_some());
+        assert!(cache.get("run").await.unwrap().0.redirect.is_none());
+
+        // a sense that's only "plural of mouse" leads to "mouse"
+        let word = cache.lookup("mice").await.unwrap();
+        assert_eq!(word.headword, "mouse");
+        assert_eq!(
+            word.dictionaries[0].definitions[0][0].meaning,
+            "A small rodent."
+        );
+
+        assert!(cache.lookup("cta").await.is_none());
+
+        // base forms nobody has are remembered like any other miss, even
+        // when nobody has looked them up themselves
+        assert!(cache.get_miss(&miss_key("dgo")).await.is_none());
+        assert!(cache.lookup("dgos").await.is_none());
+        assert!(cache.get_miss(&miss_key("dgo")).await.is_some());
+    }
+
+    #[rocket::async_test]
+    async fn base_forms_share_a_scrape_with_the_word_itself() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let lexicon = Arc::new(
+            Stub::new("lexicon", {
+                let runs = runs.clone();
+                move |word| match word {
+                    "run" => {
+                        runs.fetch_add(1, Ordering::SeqCst);
+                        Ok(defined_as("To move quickly on foot."))
+                    }
+                    _ => Err(ScrapeError::Status(StatusCode::NOT_FOUND)),
+                }
+            })
+            .delayed(100),
+        );
+        let sources = SourceRegistry::new(vec![lexicon], Arc::default(), &ScrapeConfig::default());
+        let cache = WordCache::new(
+            Arc::new(MemoryStorage::default()),
+            Arc::new(sources),
+            &CacheConfig::default(),
+        );
+
+        // "running" misses after 100ms and then asks about "run", which
+        // someone looks up themselves halfway through
+        let (running, run) = tokio::join!(cache.lookup("running"), async {
+            tokio::time::sleep(Duration::from_millis(150)).await;
+            cache.lookup("run").await
+        });
+        assert_eq!(running.unwrap().headword, "run");
+        assert!(run.unwrap().redirect.is_none());
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[rocket::async_test]
+    async fn base_forms_are_looked_up_within_the_deadline() {
+        let lexicon = Arc::new(
+            Stub::new("lexicon", |word| match word {
+                "run" => Ok(defined_as("To move quickly on foot.")),
+                _ => Err(ScrapeError::Status(StatusCode::NOT_FOUND)),
+            })
+            .delayed(100),
+        );
+        let sources = SourceRegistry::new(
+            vec![lexicon.clone()],
+            Arc::default(),
+            &ScrapeConfig {
+                deadline_ms: 150,
+                ..Default::default()
+            },
+        );
+        let cache = WordCache::new(
+            Arc::new(MemoryStorage::default()),
+            Arc::new(sources),
+            &CacheConfig::default(),
+        );
+
+        // "running" takes 100ms to miss, which leaves 50ms for "run"
+        let start = Instant::now();
+        assert!(cache.lookup("running").await.is_none());
+        assert!(start.elapsed() < Duration::from_millis(250));
+        // every candidate was asked at once
+        assert_eq!(lexicon.scrapes(), 1 + lemma::MAX_TRIES);
+        // giving up isn't the same as nobody having it
+        assert!(cache.get_miss(&miss_key("running")).await.is_none());
+    }
+
+    #[rocket::async_test]
+    async fn cached_words_are_suggested() {
+        let storage = Arc::new(MemoryStorage::default());
+        let cache = WordCache::new(storage.clone(), Arc::default(), &CacheConfig::default());
+        assert!(!cache.suggest("fonetik").contains(&"phonetic".to_string()));
+
+        cache.insert("phonetic", &entry(now())).await;
+        assert_eq!(cache.suggest("fonetik")[0], "phonetic");
+
+        // and a restart learns them all again
+        let restarted = WordCache::new(storage, Arc::default(), &CacheConfig::default());
+        restarted.learn_cached().await;
+        assert_eq!(restarted.suggest("fonetik")[0], "phonetic");
+    }
+
+    #[rocket::async_test]
+    async fn unreadable_entries_are_dropped() {
+        let storage = Arc::new(MemoryStorage::default());
+        storage.set("word:dog", "{\"overview\":[]}").await.unwrap();
+
+        let cache = WordCache::new(storage.clone(), Arc::default(), &CacheConfig::default());
+        assert!(cache.get("dog").await.is_none());
+        assert_eq!(storage.get("word:dog").await.unwrap(), None);
+    }
+}
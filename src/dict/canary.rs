@@ -0,0 +1,361 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use rocket::{fairing::AdHoc, futures::future::join_all, Build, Orbit, Rocket};
+use serde::{Deserialize, Serialize};
+use tokio::time::timeout;
+
+use super::{
+    scrape::ScrapeError,
+    sources::{DictionarySource, SourceRegistry},
+    storage::Storage,
+};
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub(crate) struct CanaryConfig {
+    pub(crate) enabled: bool,
+    /// How often every source is checked.
+    pub(crate) interval_secs: u64,
+    /// Words every source should know, so that not finding them means
+    /// something broke.
+    pub(crate) words: Vec<String>,
+    /// How many runs to remember for each source.
+    pub(crate) history: usize,
+}
+
+impl Default for CanaryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_secs: 60 * 60 * 6,
+            words: vec![
+                "dog".to_string(),
+                "run".to_string(),
+                "beautiful".to_string(),
+            ],
+            history: 28,
+        }
+    }
+}
+
+/// What went wrong when the canary looked up one word.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct CanaryFailure {
+    pub(crate) word: String,
+    pub(crate) problem: String,
+    /// The selector that stopped matching, when that's what broke.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) selector: Option<&'static str>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct CanaryRun {
+    /// When the run finished, in milliseconds since the epoch.
+    pub(crate) at: u64,
+    pub(crate) passed: bool,
+    pub(crate) failures: Vec<CanaryFailure>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct CanaryStatus {
+    pub(crate) id: &'static str,
+    pub(crate) name: &'static str,
+    /// Missing until the first run is done.
+    pub(crate) passing: Option<bool>,
+    /// When the current run of failures started, which is remembered
+    /// across restarts and however long the history is.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) failing_since: Option<u64>,
+    /// Newest first.
+    pub(crate) history: Vec<CanaryRun>,
+}
+
+/// Looks a few well-known words up in every source every so often, so a
+/// site changing its layout is noticed before anyone has to report it.
+#[derive(Clone)]
+pub(crate) struct Canary {
+    sources: Arc<SourceRegistry>,
+    /// Where each failing source's `failing_since` is kept.
+    storage: Arc<dyn Storage>,
+    words: Arc<Vec<String>>,
+    history_len: usize,
+    history: Arc<RwLock<HashMap<&'static str, VecDeque<CanaryRun>>>>,
+}
+
+impl Canary {
+    pub(crate) fn new(
+        sources: Arc<SourceRegistry>,
+        storage: Arc<dyn Storage>,
+        config: &CanaryConfig,
+    ) -> Self {
+        Self {
+            sources,
+            storage,
+            words: Arc::new(config.words.clone()),
+            history_len: config.history.max(1),
+            history: Arc::default(),
+        }
+    }
+
+    pub(crate) fn fairing() -> AdHoc {
+        AdHoc::try_on_ignite("Canary", |rocket: Rocket<Build>| async move {
+            let config: CanaryConfig = match rocket.figment().extract_inner("canary") {
+                Ok(config) => config,
+                Err(err) if err.missing() => CanaryConfig::default(),
+                Err(err) => {
+                    error!("invalid canary config: {}", err);
+                    return Err(rocket);
+                }
+            };
+            let interval = Duration::from_secs(config.interval_secs.max(1));
+
+            let Some(sources) = rocket.state::<Arc<SourceRegistry>>().cloned() else {
+                error!("the canary needs sources to be attached first");
+                return Err(rocket);
+            };
+
+            let Some(storage) = rocket.state::<Arc<dyn Storage>>().cloned() else {
+                error!("the canary needs storage to be attached first");
+                return Err(rocket);
+            };
+
+            let rocket = rocket.manage(Canary::new(sources, storage, &config));
+            if !config.enabled {
+                return Ok(rocket);
+            }
+
+            Ok(rocket.attach(AdHoc::on_liftoff("Canary", move |rocket| {
+                Box::pin(async move { Canary::keep_watch(rocket, interval) })
+            })))
+        })
+    }
+
+    fn keep_watch(rocket: &Rocket<Orbit>, interval: Duration) {
+        let canary = rocket.state::<Canary>().unwrap().clone();
+        let mut shutdown = rocket.shutdown();
+
+        tokio::spawn(async move {
+            loop {
+                canary.run().await;
+
+                tokio::select! {
+                    _ = &mut shutdown => break,
+                    _ = tokio::time::sleep(interval) => {},
+                }
+            }
+        });
+    }
+
+    /// Checks every source against every word, alerting about any source
+    /// that has just started failing.
+    pub(crate) async fn run(&self) {
+        let checks = self.sources.iter().map(|source| async move {
+            let mut failures = Vec::new();
+            for word in self.words.iter() {
+                if let Some(failure) = self.check(source.as_ref(), word).await {
+                    failures.push(failure);
+                }
+            }
+            (source.id(), failures)
+        });
+        let results = join_all(checks).await;
+
+        let at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_millis() as u64;
+
+        for (id, failures) in results {
+            let failing_since = self.failing_since(id).await;
+
+            if failures.is_empty() {
+                if failing_since.is_some() {
+                    info!("canary: `{}` is working again", id);
+                    if let Err(err) = self.storage.delete(&failing_since_key(id)).await {
+                        warn!(
+                            "canary: couldn't forget when `{}` started failing: {}",
+                            id, err
+                        );
+                    }
+                }
+            } else if failing_since.is_none() {
+                for failure in &failures {
+                    error!(
+                        "canary: `{}` broke on `{}`: {}",
+                        id, failure.word, failure.problem
+                    );
+                }
+                let key = failing_since_key(id);
+                if let Err(err) = self.storage.set(&key, &at.to_string()).await {
+                    warn!(
+                        "canary: couldn't remember when `{}` started failing: {}",
+                        id, err
+                    );
+                }
+            } else {
+                warn!(
+                    "canary: `{}` is still broken ({} of {} words failing)",
+                    id,
+                    failures.len(),
+                    self.words.len()
+                );
+            }
+
+            let mut history = self.history.write().unwrap();
+            let runs = history.entry(id).or_default();
+            runs.push_front(CanaryRun {
+                at,
+                passed: failures.is_empty(),
+                failures,
+            });
+            runs.truncate(self.history_len);
+        }
+    }
+
+    /// Looks `word` up in `source`, skipping its breaker, and says what's
+    /// wrong with the result, if anything.
+    async fn check(&self, source: &dyn DictionarySource, word: &str) -> Option<CanaryFailure> {
+        let scrape = source.scrape(&self.sources.http, self.sources.upstream(source), word);
+        let (problem, selector) = match timeout(self.sources.timeout(source), scrape).await {
+            Ok(Ok(result)) => (source.expected().shortfall(&result)?, None),
+            Ok(Err(err @ ScrapeError::MissingSelector(selector))) => {
+                (err.to_string(), Some(selector))
+            }
+            Ok(Err(err)) => (err.to_string(), None),
+            Err(_) => ("took too long".to_string(), None),
+        };
+
+        Some(CanaryFailure {
+            word: word.to_string(),
+            problem,
+            selector,
+        })
+    }
+
+    /// When `id` started failing, if it's failing.
+    async fn failing_since(&self, id: &str) -> Option<u64> {
+        match self.storage.get(&failing_since_key(id)).await {
+            Ok(since) => since?.parse().ok(),
+            Err(err) => {
+                warn!(
+                    "canary: couldn't read when `{}` started failing: {}",
+                    id, err
+                );
+                None
+            }
+        }
+    }
+
+    /// How every source has been doing, in registry order.
+    pub(crate) async fn status(&self) -> Vec<CanaryStatus> {
+        let mut statuses = Vec::new();
+        for source in self.sources.iter() {
+            let failing_since = self.failing_since(source.id()).await;
+
+            let history = self.history.read().unwrap();
+            let runs = history.get(source.id());
+            statuses.push(CanaryStatus {
+                id: source.id(),
+                name: source.name(),
+                passing: runs.and_then(|runs| runs.front()).map(|run| run.passed),
+                failing_since,
+                history: runs
+                    .map(|runs| runs.iter().cloned().collect())
+                    .unwrap_or_default(),
+            });
+        }
+        statuses
+    }
+}
+
+fn failing_since_key(id: &str) -> String {
+    format!("canary-failing-since:{}", id)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use super::*;
+    use crate::dict::{
+        sources::{
+            fixtures::{defined_as, Stub},
+            Expected, ScrapeConfig, SourceResult,
+        },
+        storage::MemoryStorage,
+    };
+
+    #[rocket::async_test]
+    async fn notices_when_a_source_breaks() {
+        // knows every word, until its layout "changes"
+        let broken = Arc::new(AtomicBool::new(false));
+        let fickle = Stub::new("fickle", {
+            let broken = broken.clone();
+            move |word| {
+                if broken.load(Ordering::Relaxed) {
+                    return Err(ScrapeError::MissingSelector(".definitions"));
+                }
+                Ok(defined_as(format!("a {}", word)))
+            }
+        });
+        // answers, but never with anything
+        let empty = Stub::new("empty", |_| Ok(SourceResult::default())).expecting(Expected {
+            origins: 1,
+            ..Default::default()
+        });
+        let sources = Arc::new(SourceRegistry::new(
+            vec![Arc::new(fickle), Arc::new(empty)],
+            Arc::default(),
+            &ScrapeConfig::default(),
+        ));
+        let storage = Arc::new(MemoryStorage::default());
+        let config = CanaryConfig {
+            words: vec!["dog".to_string(), "run".to_string()],
+            history: 2,
+            ..Default::default()
+        };
+        let canary = Canary::new(sources.clone(), storage.clone(), &config);
+
+        assert_eq!(canary.status().await[0].passing, None);
+
+        canary.run().await;
+        let status = canary.status().await;
+        assert_eq!(status[0].passing, Some(true));
+        assert_eq!(status[1].passing, Some(false));
+        assert_eq!(
+            status[1].history[0].failures[0].problem,
+            "found 0 origins, expected at least 1"
+        );
+
+        broken.store(true, Ordering::Relaxed);
+        canary.run().await;
+        let broke_at = canary.status().await[0].history[0].at;
+        tokio::time::sleep(Duration::from_millis(2)).await;
+        canary.run().await;
+        canary.run().await;
+
+        // the run it broke on has dropped out of the history by now
+        let status = canary.status().await;
+        assert_eq!(status[0].passing, Some(false));
+        assert_eq!(status[0].history.len(), 2);
+        assert!(status[0].history.iter().all(|run| run.at != broke_at));
+        assert_eq!(status[0].failing_since, Some(broke_at));
+
+        let failures = &status[0].history[0].failures;
+        assert_eq!(failures.len(), 2);
+        assert_eq!(failures[0].word, "dog");
+        assert_eq!(failures[0].selector, Some(".definitions"));
+
+        // and a restart still knows when
+        let restarted = Canary::new(sources, storage, &config);
+        assert_eq!(restarted.status().await[0].failing_since, Some(broke_at));
+
+        broken.store(false, Ordering::Relaxed);
+        restarted.run().await;
+        assert_eq!(restarted.status().await[0].failing_since, None);
+    }
+}
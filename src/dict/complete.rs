@@ -0,0 +1,214 @@
+use std::{cmp::Reverse, collections::HashMap, sync::RwLock};
+
+use super::{spelling::WORD_LIST, RESTRICTOR};
+
+/// How many completions a prefix gets.
+pub(crate) const MAX_COMPLETIONS: usize = 8;
+
+/// A word that can be completed to.
+struct Entry {
+    word: String,
+    /// How many times it has been looked up.
+    lookups: i64,
+    /// How common it is in the bundled word list, higher being more common,
+    /// or 0 if it isn't in it.
+    commonness: usize,
+}
+
+impl Entry {
+    /// Bigger is better, and the word itself settles ties.
+    fn rank(&self) -> (Reverse<i64>, Reverse<usize>, &str) {
+        (Reverse(self.lookups), Reverse(self.commonness), &self.word)
+    }
+}
+
+#[derive(Default)]
+struct Node {
+    children: Vec<(char, usize)>,
+    /// The best entries starting with this node's prefix, best first. A
+    /// word's popularity only ever goes up, so nothing that drops out of
+    /// here has to come back unless it's bumped again.
+    best: Vec<usize>,
+}
+
+#[derive(Default)]
+struct Trie {
+    /// The root, for the empty prefix, is the first node.
+    nodes: Vec<Node>,
+    entries: Vec<Entry>,
+    /// Entries by lowercased word.
+    by_word: HashMap<String, usize>,
+}
+
+impl Trie {
+    /// The entry for `word`, added if it's new.
+    fn entry(&mut self, word: &str) -> usize {
+        let lower = word.to_lowercase();
+        if let Some(&entry) = self.by_word.get(&lower) {
+            return entry;
+        }
+        self.entries.push(Entry {
+            word: word.to_string(),
+            lookups: 0,
+            commonness: 0,
+        });
+        let entry = self.entries.len() - 1;
+        self.by_word.insert(lower, entry);
+        entry
+    }
+
+    /// Puts `entry` in its place along every prefix of its word, after it
+    /// was added or got more popular.
+    fn rerank(&mut self, entry: usize) {
+        let lower = self.entries[entry].word.to_lowercase();
+
+        let mut node = 0;
+        for c in lower.chars() {
+            node = match self.nodes[node].children.iter().find(|(key, _)| *key == c) {
+                Some(&(_, child)) => child,
+                None => {
+                    self.nodes.push(Node::default());
+                    let child = self.nodes.len() - 1;
+                    self.nodes[node].children.push((c, child));
+                    child
+                }
+            };
+
+            let entries = &self.entries;
+            let best = &mut self.nodes[node].best;
+            if !best.contains(&entry) {
+                best.push(entry);
+            }
+            best.sort_by(|a, b| entries[*a].rank().cmp(&entries[*b].rank()));
+            best.truncate(MAX_COMPLETIONS);
+        }
+    }
+
+    fn complete(&self, prefix: &str) -> Vec<String> {
+        let mut node = 0;
+        for c in prefix.chars() {
+            let Some(&(_, child)) = self.nodes[node].children.iter().find(|(key, _)| *key == c)
+            else {
+                return Vec::new();
+            };
+            node = child;
+        }
+        self.nodes[node]
+            .best
+            .iter()
+            .map(|&entry| self.entries[entry].word.clone())
+            .collect()
+    }
+}
+
+/// Completes what's been typed into the search box to whole words, out of
+/// the bundled word list and every word in the cache, the most looked up
+/// first. Each prefix keeps its best completions, so answering is as quick
+/// as walking the prefix.
+pub(crate) struct Completer {
+    trie: RwLock<Trie>,
+}
+
+impl Default for Completer {
+    fn default() -> Self {
+        let mut trie = Trie {
+            nodes: vec![Node::default()],
+            ..Default::default()
+        };
+        let words: Vec<_> = WORD_LIST
+            .lines()
+            .filter(|word| !word.is_empty() && !RESTRICTOR.is_restricted(word))
+            .collect();
+        for (rank, word) in words.iter().enumerate() {
+            let entry = trie.entry(word);
+            trie.entries[entry].commonness = words.len() - rank;
+            trie.rerank(entry);
+        }
+        Self {
+            trie: RwLock::new(trie),
+        }
+    }
+}
+
+impl Completer {
+    /// Remembers that `word` is a word, so it can be completed to.
+    pub(crate) fn learn(&self, word: &str) {
+        self.counted(word, 0);
+    }
+
+    /// Remembers that `word` has been looked up `lookups` times.
+    pub(crate) fn counted(&self, word: &str, lookups: i64) {
+        if word.is_empty() || RESTRICTOR.is_restricted(&word.to_lowercase()) {
+            return;
+        }
+        let mut trie = self.trie.write().unwrap();
+        let known = trie.by_word.contains_key(&word.to_lowercase());
+        let entry = trie.entry(word);
+        if known && lookups <= trie.entries[entry].lookups {
+            return;
+        }
+        trie.entries[entry].lookups = trie.entries[entry].lookups.max(lookups);
+        trie.rerank(entry);
+    }
+
+    /// The most popular words starting with `prefix`, ignoring case.
+    pub(crate) fn complete(&self, prefix: &str) -> Vec<String> {
+        let prefix = prefix.to_lowercase();
+        if prefix.is_empty() {
+            return Vec::new();
+        }
+        self.trie.read().unwrap().complete(&prefix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn common_words_come_first() {
+        let completer = Completer::default();
+        let completions = completer.complete("th");
+        assert_eq!(completions.len(), MAX_COMPLETIONS);
+        assert_eq!(completions[..3], ["the", "that", "they"]);
+        assert_eq!(completer.complete("TH"), completions);
+
+        assert!(completer.complete("").is_empty());
+        assert!(completer.complete("qqq").is_empty());
+
+        // the word list is only the books, not their license
+        assert!(!completer.complete("eb").contains(&"ebook".to_string()));
+        assert!(!completer.complete("gu").contains(&"gutenberg".to_string()));
+    }
+
+    #[test]
+    fn lookups_outrank_commonness() {
+        let completer = Completer::default();
+        assert!(!completer.complete("th").contains(&"thesaurus".to_string()));
+
+        completer.counted("thesaurus", 3);
+        assert_eq!(completer.complete("th")[0], "thesaurus");
+        assert_eq!(completer.complete("thes")[0], "thesaurus");
+
+        // counts only go up, since they come from counters
+        completer.counted("thesaurus", 1);
+        assert_eq!(completer.complete("th")[0], "thesaurus");
+
+        completer.counted("the", 4);
+        assert_eq!(completer.complete("th")[..2], ["the", "thesaurus"]);
+    }
+
+    #[test]
+    fn learned_and_restricted_words() {
+        let completer = Completer::default();
+        completer.learn("Zyzzyva");
+        assert_eq!(completer.complete("zyz"), ["Zyzzyva"]);
+
+        completer.counted("nakedness", 100);
+        assert!(completer
+            .complete("nak")
+            .iter()
+            .all(|word| !word.contains("naked")));
+        assert!(completer.complete("naked").is_empty());
+    }
+}
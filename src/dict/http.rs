@@ -0,0 +1,368 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use reqwest::{Client, Proxy, StatusCode, Url};
+use rocket::{fairing::AdHoc, Build, Rocket};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    politeness::{parse_retry_after, HostLimiter, Politeness},
+    scrape::ScrapeError,
+};
+
+#[derive(Deserialize, Default, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum HttpMode {
+    /// Fetch pages from the sources as usual.
+    #[default]
+    Live,
+    /// Fetch pages, saving every response that comes back.
+    Record,
+    /// Serve saved responses without touching the network.
+    Replay,
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub(crate) struct HttpConfig {
+    pub(crate) mode: HttpMode,
+    /// Where recordings are saved to and replayed from.
+    pub(crate) dir: PathBuf,
+    /// What we tell the sources we are.
+    pub(crate) user_agent: String,
+    /// Whether to ask for gzip and brotli compressed pages.
+    pub(crate) compression: bool,
+    /// Something like `http://proxy:8080` or `socks5://proxy:1080` to send
+    /// every request through.
+    pub(crate) proxy: Option<String>,
+    /// How many idle connections the pool keeps around for each site. This
+    /// doesn't limit connections in use, which is `Politeness::max_concurrent`.
+    pub(crate) max_idle_per_host: usize,
+    /// How long an idle connection is kept open.
+    pub(crate) idle_timeout_secs: u64,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            mode: HttpMode::default(),
+            dir: PathBuf::from("recordings"),
+            user_agent: concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION")).to_string(),
+            compression: true,
+            proxy: None,
+            max_idle_per_host: 4,
+            idle_timeout_secs: 90,
+        }
+    }
+}
+
+/// A response from a source, as record mode saves it.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Recording {
+    pub(crate) url: String,
+    /// Where the site sent the request on to, if it did.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) redirected_to: Option<String>,
+    pub(crate) status: u16,
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) body: String,
+}
+
+impl Recording {
+    /// How long the site asked us to wait before trying again, if it did.
+    fn retry_after(&self) -> Option<Duration> {
+        let (_, value) = self
+            .headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("retry-after"))?;
+        parse_retry_after(value, SystemTime::now())
+    }
+}
+
+/// A page a source fetched.
+pub(crate) struct Page {
+    pub(crate) body: String,
+    /// Where the site sent the request on to, if it did.
+    pub(crate) redirected_to: Option<String>,
+}
+
+/// How sources get their pages: from the network, from the network while
+/// saving everything, or from what was saved before. There's one of these,
+/// so every source shares the same client and its connections, and every
+/// request to a host goes through the same limits.
+pub(crate) struct Http {
+    client: Client,
+    hosts: Mutex<HashMap<String, Arc<HostLimiter>>>,
+    mode: HttpMode,
+    dir: PathBuf,
+}
+
+impl Http {
+    pub(crate) fn new(config: &HttpConfig) -> Result<Self, reqwest::Error> {
+        let mut client = Client::builder()
+            .user_agent(&config.user_agent)
+            .gzip(config.compression)
+            .brotli(config.compression)
+            .pool_max_idle_per_host(config.max_idle_per_host)
+            .pool_idle_timeout(Duration::from_secs(config.idle_timeout_secs));
+        if let Some(proxy) = &config.proxy {
+            client = client.proxy(Proxy::all(proxy)?);
+        }
+
+        Ok(Self {
+            client: client.build()?,
+            hosts: Mutex::new(HashMap::new()),
+            mode: config.mode,
+            dir: config.dir.clone(),
+        })
+    }
+
+    /// Manages an `Arc<Http>` set up by the `http` config. This has to be
+    /// attached before the sources.
+    pub(crate) fn fairing() -> AdHoc {
+        AdHoc::try_on_ignite("HTTP", |rocket: Rocket<Build>| async move {
+            let config: HttpConfig = match rocket.figment().extract_inner("http") {
+                Ok(config) => config,
+                Err(err) if err.missing() => HttpConfig::default(),
+                Err(err) => {
+                    error!("invalid http config: {}", err);
+                    return Err(rocket);
+                }
+            };
+
+            if config.mode != HttpMode::Live {
+                info!("{:?}ing responses in {}", config.mode, config.dir.display());
+            }
+
+            match Http::new(&config) {
+                Ok(http) => Ok(rocket.manage(Arc::new(http))),
+                Err(err) => {
+                    error!("couldn't set up the http client: {}", err);
+                    Err(rocket)
+                }
+            }
+        })
+    }
+
+    /// Gets a page, treating anything but a 2xx as an error. Requests keep
+    /// to the limits in `politeness` for the page's host, and transient
+    /// failures are retried.
+    pub(crate) async fn fetch(
+        &self,
+        url: &str,
+        politeness: &Politeness,
+    ) -> Result<Page, ScrapeError> {
+        let recording = match self.mode {
+            HttpMode::Live => self.download_politely(url, politeness).await?,
+            HttpMode::Record => {
+                let recording = self.download_politely(url, politeness).await?;
+                if let Err(err) = self.save(&recording).await {
+                    warn!("couldn't record {}: {}", url, err);
+                }
+                recording
+            }
+            HttpMode::Replay => self.load(url).await?,
+        };
+
+        let status =
+            StatusCode::from_u16(recording.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        if !status.is_success() {
+            return Err(ScrapeError::Status(status));
+        }
+        Ok(Page {
+            body: recording.body,
+            redirected_to: recording.redirected_to,
+        })
+    }
+
+    /// The limiter for `url`'s host, set up with `politeness` by whichever
+    /// request gets there first.
+    fn limiter(&self, url: &str, politeness: &Politeness) -> Arc<HostLimiter> {
+        let host = Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+            .unwrap_or_default();
+        self.hosts
+            .lock()
+            .unwrap()
+            .entry(host)
+            .or_insert_with(|| Arc::new(HostLimiter::new(politeness)))
+            .clone()
+    }
+
+    async fn download_politely(
+        &self,
+        url: &str,
+        politeness: &Politeness,
+    ) -> Result<Recording, ScrapeError> {
+        let limiter = self.limiter(url, politeness);
+
+        let mut attempt = 0;
+        loop {
+            let result = limiter.run(self.download(url, politeness)).await;
+
+            let transient = match &result {
+                Ok(recording) => {
+                    let status = recording.status;
+                    (status == 429 || (500..600).contains(&status)).then(|| recording.retry_after())
+                }
+                Err(ScrapeError::Fetch(err)) if err.is_timeout() || err.is_connect() => Some(None),
+                Err(_) => None,
+            };
+            let Some(delay) =
+                transient.and_then(|retry_after| politeness.retry_delay(attempt, retry_after))
+            else {
+                return result;
+            };
+
+            info!("retrying {} in {}ms", url, delay.as_millis());
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    async fn download(&self, url: &str, politeness: &Politeness) -> Result<Recording, ScrapeError> {
+        let response = self
+            .client
+            .get(url)
+            .timeout(politeness.attempt_timeout())
+            .send()
+            .await
+            .map_err(ScrapeError::Fetch)?;
+        let redirected_to = Some(response.url().to_string()).filter(|to| to != url);
+        let status = response.status().as_u16();
+        let headers = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        let body = response.text().await.map_err(ScrapeError::Fetch)?;
+
+        Ok(Recording {
+            url: url.to_string(),
+            redirected_to,
+            status,
+            headers,
+            body,
+        })
+    }
+
+    /// Where the recording for `url` lives: the url minus its scheme, with
+    /// anything that doesn't belong in a file name replaced.
+    fn path(&self, url: &str) -> PathBuf {
+        let name: String = url
+            .split_once("://")
+            .map_or(url, |(_, rest)| rest)
+            .chars()
+            .map(|c| match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' => c,
+                _ => '_',
+            })
+            .collect();
+        self.dir.join(format!("{}.json", name))
+    }
+
+    pub(crate) async fn save(&self, recording: &Recording) -> std::io::Result<()> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+        let json = serde_json::to_string_pretty(recording)?;
+        tokio::fs::write(self.path(&recording.url), json + "\n").await
+    }
+
+    async fn load(&self, url: &str) -> Result<Recording, ScrapeError> {
+        let json = tokio::fs::read_to_string(self.path(url))
+            .await
+            .map_err(|_| ScrapeError::NotRecorded(url.to_string()))?;
+        serde_json::from_str::<Recording>(&json)
+            .ok()
+            // two urls can end up with the same file name
+            .filter(|recording| recording.url == url)
+            .ok_or_else(|| ScrapeError::NotRecorded(url.to_string()))
+    }
+}
+
+impl Default for Http {
+    fn default() -> Self {
+        Self::new(&HttpConfig::default()).expect("the default http config is valid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replay(dir: PathBuf) -> Http {
+        Http::new(&HttpConfig {
+            mode: HttpMode::Replay,
+            dir,
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn proxies_can_be_http_or_socks() {
+        for proxy in ["http://localhost:8080", "socks5://localhost:1080"] {
+            let config = HttpConfig {
+                proxy: Some(proxy.to_string()),
+                ..Default::default()
+            };
+            assert!(Http::new(&config).is_ok(), "couldn't use {}", proxy);
+        }
+    }
+
+    #[rocket::async_test]
+    async fn replays_what_was_recorded() {
+        let dir = std::env::temp_dir().join(format!("dict-recordings-{}", std::process::id()));
+        let http = replay(dir.clone());
+
+        let url = "https://www.example.com/word/dog?lang=en";
+        assert!(matches!(
+            http.fetch(url, &Politeness::default()).await,
+            Err(ScrapeError::NotRecorded(_))
+        ));
+
+        let recording = Recording {
+            url: url.to_string(),
+            redirected_to: None,
+            status: 200,
+            headers: vec![("content-type".to_string(), "text/html".to_string())],
+            body: "<h1>dog</h1>".to_string(),
+        };
+        http.save(&recording).await.unwrap();
+        assert_eq!(
+            http.fetch(url, &Politeness::default()).await.unwrap().body,
+            "<h1>dog</h1>"
+        );
+
+        let missing = "https://www.example.com/word/cat";
+        http.save(&Recording {
+            url: missing.to_string(),
+            redirected_to: None,
+            status: 404,
+            headers: Vec::new(),
+            body: String::new(),
+        })
+        .await
+        .unwrap();
+        assert!(matches!(
+            http.fetch(missing, &Politeness::default()).await,
+            Err(ScrapeError::Status(StatusCode::NOT_FOUND))
+        ));
+
+        // a different url that happens to map to the same file
+        assert!(matches!(
+            http.fetch(
+                "https://www.example.com/word/dog&lang=en",
+                &Politeness::default()
+            )
+            .await,
+            Err(ScrapeError::NotRecorded(_))
+        ));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
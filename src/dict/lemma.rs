@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use super::Word;
+
+/// How many base forms are tried before giving up, since each one can mean
+/// asking every source again.
+pub(crate) const MAX_TRIES: usize = 3;
+
+/// Inflected forms that no suffix rule gets back to their base form.
+#[rustfmt::skip]
+const IRREGULAR: &[(&str, &str)] = &[
+    ("am", "be"), ("is", "be"), ("are", "be"), ("was", "be"), ("were", "be"), ("been", "be"),
+    ("has", "have"), ("had", "have"),
+    ("does", "do"), ("did", "do"), ("done", "do"),
+    ("went", "go"), ("gone", "go"),
+    ("ate", "eat"), ("eaten", "eat"),
+    ("began", "begin"), ("begun", "begin"),
+    ("bought", "buy"), ("brought", "bring"), ("built", "build"), ("caught", "catch"),
+    ("broke", "break"), ("broken", "break"),
+    ("came", "come"),
+    ("chose", "choose"), ("chosen", "choose"),
+    ("drank", "drink"), ("drunk", "drink"),
+    ("drew", "draw"), ("drawn", "draw"),
+    ("drove", "drive"), ("driven", "drive"),
+    ("fell", "fall"), ("fallen", "fall"),
+    ("felt", "feel"), ("found", "find"),
+    ("flew", "fly"), ("flown", "fly"),
+    ("forgot", "forget"), ("forgotten", "forget"),
+    ("gave", "give"), ("given", "give"),
+    ("got", "get"), ("gotten", "get"),
+    ("grew", "grow"), ("grown", "grow"),
+    ("held", "hold"), ("kept", "keep"),
+    ("knew", "know"), ("known", "know"),
+    ("left", "leave"), ("lost", "lose"), ("made", "make"), ("met", "meet"), ("paid", "pay"),
+    ("ran", "run"),
+    ("said", "say"),
+    ("sang", "sing"), ("sung", "sing"),
+    ("sat", "sit"),
+    ("saw", "see"), ("seen", "see"),
+    ("sent", "send"), ("slept", "sleep"), ("spent", "spend"),
+    ("spoke", "speak"), ("spoken", "speak"),
+    ("stood", "stand"),
+    ("swam", "swim"), ("swum", "swim"),
+    ("taught", "teach"), ("thought", "think"), ("told", "tell"),
+    ("took", "take"), ("taken", "take"),
+    ("threw", "throw"), ("thrown", "throw"),
+    ("understood", "understand"), ("won", "win"),
+    ("wore", "wear"), ("worn", "wear"),
+    ("wrote", "write"), ("written", "write"),
+    ("better", "good"), ("best", "good"), ("worse", "bad"), ("worst", "bad"),
+    ("children", "child"), ("feet", "foot"), ("geese", "goose"), ("lice", "louse"),
+    ("men", "man"), ("mice", "mouse"), ("oxen", "ox"), ("people", "person"), ("teeth", "tooth"),
+    ("women", "woman"),
+    ("analyses", "analysis"), ("cacti", "cactus"), ("crises", "crisis"), ("criteria", "criterion"),
+    ("fungi", "fungus"), ("indices", "index"), ("larvae", "larva"), ("phenomena", "phenomenon"),
+];
+
+lazy_static! {
+    static ref IRREGULARS: HashMap<&'static str, &'static str> = IRREGULAR.iter().copied().collect();
+
+    /// A sense that only says what the word is an inflection of, the way
+    /// Wiktionary words them: "plural of dog", "simple past tense and past
+    /// participle of go", maybe after a label like "(archaic)".
+    static ref FORM_OF: Regex = Regex::new(concat!(
+        r"^(?:\([^)]*\)\s*)?",
+        r"(?:(?:simple|present|past|third-person|singular|plural|comparative|superlative|",
+        r"tense|participle|and|form|degree|indicative|gerund) )*",
+        r"(?:plural|tense|participle|comparative|superlative|gerund|form) of ",
+        r"([\p{L}'’-]+(?: [\p{L}'’-]+)*)",
+    ))
+    .unwrap();
+
+    static ref TAG: Regex = Regex::new(r"<[^>]*>").unwrap();
+}
+
+/// The base forms `word` might be an inflection of, most likely first. Only
+/// the first word of a phrase is changed, so "kicked the bucket" can become
+/// "kick the bucket".
+pub(crate) fn candidates(word: &str) -> Vec<String> {
+    let (first, rest) = match word.split_once(' ') {
+        Some((first, rest)) => (first, Some(rest)),
+        None => (word, None),
+    };
+    let lower = first.to_lowercase();
+
+    let mut bases = Vec::new();
+    if let Some(base) = IRREGULARS.get(lower.as_str()) {
+        bases.push(base.to_string());
+    }
+    bases.extend(regular_bases(&lower));
+
+    let mut candidates = Vec::new();
+    for base in bases {
+        let candidate = match rest {
+            Some(rest) => format!("{} {}", base, rest),
+            None => base,
+        };
+        if candidate != word && !candidates.contains(&candidate) {
+            candidates.push(candidate);
+        }
+    }
+    candidates
+}
+
+/// What the usual English suffix rules make of `word`.
+fn regular_bases(word: &str) -> Vec<String> {
+    let stem = |suffix: &str| {
+        word.strip_suffix(suffix)
+            .filter(|stem| stem.chars().count() >= 2)
+    };
+    // "running" and "stopped" double their last consonant
+    let undoubled = |stem: &str| {
+        let mut chars = stem.chars().rev();
+        match (chars.next(), chars.next()) {
+            (Some(a), Some(b)) if a == b && !"aeiouls".contains(a) => {
+                Some(stem[..stem.len() - a.len_utf8()].to_string())
+            }
+            _ => None,
+        }
+    };
+
+    let mut bases = Vec::new();
+    if let Some(stem) = stem("ies").or_else(|| stem("ied")) {
+        bases.push(format!("{}y", stem));
+    }
+    if let Some(stem) = stem("ves") {
+        bases.push(format!("{}f", stem));
+        bases.push(format!("{}fe", stem));
+    }
+    if let Some(stem) = stem("es") {
+        if ["s", "x", "z", "ch", "sh", "o"]
+            .iter()
+            .any(|end| stem.ends_with(end))
+        {
+            bases.push(stem.to_string());
+        }
+    }
+    if let Some(stem) = stem("s").filter(|stem| !stem.ends_with('s')) {
+        bases.push(stem.to_string());
+    }
+    for suffix in ["ing", "ed", "est", "er"] {
+        let Some(stem) = stem(suffix) else {
+            continue;
+        };
+        if let Some(stem) = stem.strip_suffix('i').filter(|_| suffix.starts_with('e')) {
+            bases.push(format!("{}y", stem));
+        }
+        bases.extend(undoubled(stem));
+        // "hoped" is more likely to be "hope" than "hop", "jumped" is "jump"
+        if dropped_e(stem) {
+            bases.push(format!("{}e", stem));
+            bases.push(stem.to_string());
+        } else {
+            bases.push(stem.to_string());
+            bases.push(format!("{}e", stem));
+        }
+    }
+    bases
+}
+
+/// Whether `stem` ends consonant, vowel, consonant, which is usually where
+/// an "e" went missing.
+fn dropped_e(stem: &str) -> bool {
+    let vowel = |c: char| "aeiou".contains(c);
+    let chars: Vec<_> = stem.chars().rev().take(3).collect();
+    match chars[..] {
+        [c, v, b] => !vowel(c) && !"wxy".contains(c) && vowel(v) && !vowel(b),
+        _ => false,
+    }
+}
+
+/// The base forms `entry`'s own senses say `word` is an inflection of, like
+/// "dog" for a "dogs" whose only sense is "plural of dog", along with whether
+/// that's all its senses say. Without any senses it has nothing to say
+/// either way, which counts as all of them.
+pub(crate) fn named_base_forms(word: &str, entry: &Word) -> (Vec<String>, bool) {
+    let words = word.split_whitespace().count().max(1);
+    let mut bases = Vec::new();
+    let mut only_forms = true;
+    let senses = entry
+        .dictionaries
+        .iter()
+        .flat_map(|dict| dict.definitions.iter().flatten());
+    for sense in senses {
+        match base_form_in(&sense.meaning, words) {
+            Some(base) if !bases.contains(&base) => bases.push(base),
+            Some(_) => {}
+            None => only_forms = false,
+        }
+    }
+    (bases, only_forms)
+}
+
+/// The base form a sense like "present participle of <i>run</i>" names,
+/// which is as many words long as the inflection it's for, since whatever
+/// comes after it is more of the sense, like "used in hunting" in "plural
+/// form of dog used in hunting".
+fn base_form_in(meaning: &str, words: usize) -> Option<String> {
+    let text = TAG.replace_all(meaning, "");
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let base = FORM_OF.captures(&text)?.get(1)?.as_str();
+    Some(base.split(' ').take(words).collect::<Vec<_>>().join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suffixes_and_irregulars_lead_to_base_forms() {
+        let first = |word| candidates(word).into_iter().next();
+        let has = |word, base: &str| candidates(word).contains(&base.to_string());
+
+        assert_eq!(first("went").as_deref(), Some("go"));
+        assert_eq!(first("mice").as_deref(), Some("mouse"));
+        assert_eq!(first("running").as_deref(), Some("run"));
+        assert_eq!(first("stopped").as_deref(), Some("stop"));
+        assert!(has("dogs", "dog"));
+        assert!(has("flies", "fly"));
+        assert!(has("tried", "try"));
+        assert!(has("boxes", "box"));
+        assert!(has("wolves", "wolf"));
+        assert!(has("knives", "knife"));
+        assert!(has("jumped", "jump"));
+        assert!(has("hoped", "hope"));
+        assert!(has("making", "make"));
+        assert!(has("happier", "happy"));
+        assert!(has("biggest", "big"));
+        assert!(has("kicked the bucket", "kick the bucket"));
+
+        assert!(!has("glass", "glas"));
+        assert!(candidates("dog").is_empty());
+    }
+
+    #[test]
+    fn form_of_senses_name_their_base_form() {
+        let base = |meaning| base_form_in(meaning, 1);
+        assert_eq!(base("plural of dog").as_deref(), Some("dog"));
+        assert_eq!(
+            base("<span>present participle and gerund of <span><i>run</i> </span></span>")
+                .as_deref(),
+            Some("run")
+        );
+        assert_eq!(
+            base("simple past tense and past participle of go").as_deref(),
+            Some("go")
+        );
+        assert_eq!(
+            base("third-person singular simple present indicative form of eat").as_deref(),
+            Some("eat")
+        );
+        assert_eq!(base("(archaic) plural of ox").as_deref(), Some("ox"));
+        assert_eq!(
+            base("plural form of dog used in hunting").as_deref(),
+            Some("dog")
+        );
+        assert_eq!(
+            base_form_in("simple past tense of kick the bucket", 3).as_deref(),
+            Some("kick the bucket")
+        );
+        assert_eq!(base("A mammal of the family Canidae"), None);
+        assert_eq!(base("One who is fond of dogs"), None);
+    }
+}
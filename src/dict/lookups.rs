@@ -0,0 +1,405 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use rocket::{fairing::AdHoc, Build, Orbit, Rocket};
+use serde::{Deserialize, Serialize};
+
+use super::{complete::Completer, normalize, storage::Storage, WordCache, RESTRICTOR};
+
+const KEY_PREFIX: &str = "lookups:";
+const HOURLY_PREFIX: &str = "trend:hour:";
+const DAILY_PREFIX: &str = "trend:day:";
+
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = HOUR * 24;
+
+/// Lookups a word gets for free in the previous window when working out its
+/// growth, so a single lookup out of nowhere doesn't top the chart.
+const GROWTH_PRIOR: f64 = 3.0;
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub(crate) struct LeaderboardConfig {
+    /// How often the rankings are rebuilt from the counters.
+    pub(crate) refresh_secs: u64,
+    /// How many words make it onto the leaderboard.
+    pub(crate) size: usize,
+}
+
+impl Default for LeaderboardConfig {
+    fn default() -> Self {
+        Self {
+            refresh_secs: 60 * 5,
+            size: 100,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct WordRanking {
+    pub(crate) name: String,
+    pub(crate) count: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromFormField)]
+pub(crate) enum Window {
+    /// The last 24 hours, compared to the 24 before that.
+    Today,
+    /// The last 7 days, compared to the 7 before that.
+    Week,
+    /// Every lookup ever, ranked by volume.
+    All,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct TrendingWord {
+    pub(crate) name: String,
+    /// Lookups within the window.
+    pub(crate) count: i64,
+    /// How much faster the word is being looked up than in the window
+    /// before, which is what it's ranked by. Missing for all time.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) growth: Option<f64>,
+}
+
+#[derive(Default)]
+struct Rankings {
+    top: Arc<Vec<WordRanking>>,
+    trending: HashMap<Window, Arc<Vec<TrendingWord>>>,
+}
+
+/// Counts how many times each word has been looked up, and keeps the
+/// most looked up ones ranked.
+#[derive(Clone)]
+pub(crate) struct Lookups {
+    storage: Arc<dyn Storage>,
+    rankings: Arc<RwLock<Rankings>>,
+    size: usize,
+    /// Completes searches, most looked up first.
+    completer: Arc<Completer>,
+}
+
+impl Lookups {
+    pub(crate) fn new(storage: Arc<dyn Storage>, config: &LeaderboardConfig) -> Self {
+        Self {
+            storage,
+            rankings: Arc::default(),
+            size: config.size,
+            completer: Arc::default(),
+        }
+    }
+
+    pub(crate) fn fairing() -> AdHoc {
+        AdHoc::try_on_ignite("Lookups", |rocket: Rocket<Build>| async move {
+            let config: LeaderboardConfig = match rocket.figment().extract_inner("leaderboard") {
+                Ok(config) => config,
+                Err(err) if err.missing() => LeaderboardConfig::default(),
+                Err(err) => {
+                    error!("invalid leaderboard config: {}", err);
+                    return Err(rocket);
+                }
+            };
+            let refresh = Duration::from_secs(config.refresh_secs.max(1));
+
+            let Some(storage) = rocket.state::<Arc<dyn Storage>>().cloned() else {
+                error!("lookups need storage to be attached first");
+                return Err(rocket);
+            };
+
+            let Some(cache) = rocket.state::<WordCache>() else {
+                error!("lookups need the word cache to be attached first");
+                return Err(rocket);
+            };
+
+            let lookups = Lookups::new(storage, &config);
+            for word in cache.cached_words().await {
+                lookups.completer.learn(&word);
+            }
+
+            Ok(rocket
+                .manage(lookups)
+                .attach(AdHoc::on_liftoff("Leaderboard", move |rocket| {
+                    Box::pin(async move { Lookups::keep_fresh(rocket, refresh) })
+                })))
+        })
+    }
+
+    fn keep_fresh(rocket: &Rocket<Orbit>, refresh: Duration) {
+        let lookups = rocket.state::<Lookups>().unwrap().clone();
+        let mut shutdown = rocket.shutdown();
+
+        tokio::spawn(async move {
+            loop {
+                lookups.refresh().await;
+
+                tokio::select! {
+                    _ = &mut shutdown => break,
+                    _ = tokio::time::sleep(refresh) => {},
+                }
+            }
+        });
+    }
+
+    pub(crate) async fn record(&self, word: &str) {
+        self.record_at(word, now()).await
+    }
+
+    async fn record_at(&self, word: &str, now: u64) {
+        let word = normalize(word);
+
+        match self
+            .storage
+            .incr(&format!("{}{}", KEY_PREFIX, word), 1)
+            .await
+        {
+            Ok(count) => self.completer.counted(&word, count),
+            Err(err) => warn!("couldn't count a lookup of `{}`: {}", word, err),
+        }
+
+        let buckets = [
+            format!("{}{}:{}", HOURLY_PREFIX, now / HOUR, word),
+            format!("{}{}:{}", DAILY_PREFIX, now / DAY, word),
+        ];
+        for key in buckets {
+            if let Err(err) = self.storage.incr(&key, 1).await {
+                warn!("couldn't count a lookup of `{}`: {}", word, err);
+            }
+        }
+    }
+
+    /// Rebuilds the leaderboard and trending lists from the stored counters.
+    pub(crate) async fn refresh(&self) {
+        self.refresh_at(now()).await
+    }
+
+    async fn refresh_at(&self, now: u64) {
+        let mut top: Vec<WordRanking> = match self.storage.scan(KEY_PREFIX).await {
+            Ok(counters) => counters
+                .into_iter()
+                .filter_map(|(key, count)| {
+                    Some(WordRanking {
+                        name: key.strip_prefix(KEY_PREFIX)?.to_string(),
+                        count: count.parse().ok()?,
+                    })
+                })
+                .filter(|word| !RESTRICTOR.is_restricted(&word.name.to_lowercase()))
+                .inspect(|word| self.completer.counted(&word.name, word.count))
+                .collect(),
+            Err(err) => {
+                warn!("couldn't refresh the leaderboard: {}", err);
+                return;
+            }
+        };
+        top.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+        top.truncate(self.size);
+
+        let all_time = top
+            .iter()
+            .map(|word| TrendingWord {
+                name: word.name.clone(),
+                count: word.count,
+                growth: None,
+            })
+            .collect();
+
+        let today = self.trending(HOURLY_PREFIX, now / HOUR, 24).await;
+        let week = self.trending(DAILY_PREFIX, now / DAY, 7).await;
+
+        let mut rankings = self.rankings.write().unwrap();
+        rankings.top = Arc::new(top);
+        rankings.trending.insert(Window::All, Arc::new(all_time));
+        rankings.trending.insert(Window::Today, Arc::new(today));
+        rankings.trending.insert(Window::Week, Arc::new(week));
+    }
+
+    /// Ranks words by how their lookups in the last `len` buckets compare to
+    /// the `len` before that, and throws away buckets older than both.
+    async fn trending(&self, prefix: &str, current: u64, len: u64) -> Vec<TrendingWord> {
+        let counters = match self.storage.scan(prefix).await {
+            Ok(counters) => counters,
+            Err(err) => {
+                warn!("couldn't refresh the trending words: {}", err);
+                return Vec::new();
+            }
+        };
+
+        let mut windows: HashMap<String, (i64, i64)> = HashMap::new();
+        for (key, count) in counters {
+            let Some((bucket, word)) = key
+                .strip_prefix(prefix)
+                .and_then(|rest| rest.split_once(':'))
+            else {
+                continue;
+            };
+            let (Ok(bucket), Ok(count)) = (bucket.parse::<u64>(), count.parse::<i64>()) else {
+                continue;
+            };
+
+            let age = current.saturating_sub(bucket);
+            if age >= len * 2 {
+                if let Err(err) = self.storage.delete(&key).await {
+                    warn!("couldn't drop an old lookup counter: {}", err);
+                }
+                continue;
+            }
+
+            let (now, before) = windows.entry(word.to_string()).or_default();
+            if age < len {
+                *now += count;
+            } else {
+                *before += count;
+            }
+        }
+
+        let mut words = rank_growth(windows);
+        words.retain(|word| !RESTRICTOR.is_restricted(&word.name.to_lowercase()));
+        words.truncate(self.size);
+        words
+    }
+
+    /// The leaderboard as of the last refresh.
+    pub(crate) fn top(&self) -> Arc<Vec<WordRanking>> {
+        self.rankings.read().unwrap().top.clone()
+    }
+
+    /// The most looked up words starting with `prefix`, out of the word list
+    /// and every word that's been looked up or cached.
+    pub(crate) fn complete(&self, prefix: &str) -> Vec<String> {
+        self.completer.complete(prefix)
+    }
+
+    /// The trending words for `window` as of the last refresh.
+    pub(crate) fn trending_in(&self, window: Window) -> Arc<Vec<TrendingWord>> {
+        self.rankings
+            .read()
+            .unwrap()
+            .trending
+            .get(&window)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+/// Orders words by growth from one window to the next, dropping the ones
+/// that aren't growing at all.
+fn rank_growth(windows: HashMap<String, (i64, i64)>) -> Vec<TrendingWord> {
+    let mut words: Vec<TrendingWord> = windows
+        .into_iter()
+        .filter(|(_, (now, before))| now > before)
+        .map(|(name, (now, before))| TrendingWord {
+            name,
+            count: now,
+            growth: Some((now - before) as f64 / (before as f64 + GROWTH_PRIOR)),
+        })
+        .collect();
+
+    words.sort_by(|a, b| {
+        b.growth
+            .partial_cmp(&a.growth)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| b.count.cmp(&a.count))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    words
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dict::storage::MemoryStorage;
+
+    use super::*;
+
+    #[rocket::async_test]
+    async fn ranks_by_lookups() {
+        let lookups = Lookups::new(
+            Arc::new(MemoryStorage::default()),
+            &LeaderboardConfig::default(),
+        );
+
+        for word in ["cat", "dog", "dog", "sexy", "sexy", "sexy", "dog"] {
+            lookups.record(word).await;
+        }
+        assert!(lookups.top().is_empty());
+
+        lookups.refresh().await;
+        let top: Vec<_> = lookups
+            .top()
+            .iter()
+            .map(|word| (word.name.clone(), word.count))
+            .collect();
+        assert_eq!(top, [("dog".to_string(), 3), ("cat".to_string(), 1)]);
+    }
+
+    #[rocket::async_test]
+    async fn completes_by_lookups() {
+        let storage = Arc::new(MemoryStorage::default());
+        let lookups = Lookups::new(storage.clone(), &LeaderboardConfig::default());
+        assert_eq!(lookups.complete("do")[0], "do");
+
+        lookups.record("dog").await;
+        assert_eq!(lookups.complete("do")[0], "dog");
+
+        // counters from before a restart count too
+        storage.incr("lookups:dogma", 2).await.unwrap();
+        let restarted = Lookups::new(storage, &LeaderboardConfig::default());
+        restarted.refresh().await;
+        assert_eq!(restarted.complete("do")[..2], ["dogma", "dog"]);
+    }
+
+    #[rocket::async_test]
+    async fn trends_by_growth() {
+        let storage = Arc::new(MemoryStorage::default());
+        let lookups = Lookups::new(storage.clone(), &LeaderboardConfig::default());
+
+        let now = 1_700_000_000;
+        let yesterday = now - DAY;
+        let last_month = now - DAY * 30;
+
+        // "dog" is popular but steady, "cat" came out of nowhere
+        for _ in 0..50 {
+            lookups.record_at("dog", yesterday).await;
+            lookups.record_at("dog", now).await;
+        }
+        for _ in 0..10 {
+            lookups.record_at("cat", now).await;
+        }
+        lookups.record_at("cow", last_month).await;
+
+        lookups.refresh_at(now).await;
+
+        let today: Vec<_> = lookups
+            .trending_in(Window::Today)
+            .iter()
+            .map(|word| word.name.clone())
+            .collect();
+        assert_eq!(today, ["cat"]);
+
+        let week: Vec<_> = lookups
+            .trending_in(Window::Week)
+            .iter()
+            .map(|word| word.name.clone())
+            .collect();
+        assert_eq!(week, ["dog", "cat"]);
+
+        let all: Vec<_> = lookups
+            .trending_in(Window::All)
+            .iter()
+            .map(|word| word.name.clone())
+            .collect();
+        assert_eq!(all, ["dog", "cat", "cow"]);
+
+        // last month's buckets are cleaned up along the way
+        let daily = storage.scan(DAILY_PREFIX).await.unwrap();
+        assert!(daily.iter().all(|(key, _)| !key.ends_with(":cow")));
+    }
+}
@@ -1,17 +1,39 @@
+mod breaker;
+mod cache;
+mod canary;
+mod complete;
+pub(crate) mod http;
+mod lemma;
+mod lookups;
+mod politeness;
+mod query;
 mod restrictor;
 mod scrape;
+pub(crate) mod sources;
+mod spelling;
+pub(crate) mod storage;
 
 use std::{
+    collections::{BTreeMap, HashMap},
     fmt::Display,
-    time::{SystemTime, UNIX_EPOCH},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
+use rocket::futures::{stream::FuturesUnordered, Stream};
 use serde::{Deserialize, Serialize};
+use tokio::time::{timeout_at, Instant};
 
+pub(crate) use breaker::*;
+pub(crate) use cache::*;
+pub(crate) use canary::*;
+pub(crate) use lookups::*;
+pub(crate) use query::*;
 pub(crate) use restrictor::*;
-pub(crate) use scrape::*;
 
-#[derive(Debug, Serialize, Deserialize)]
+use scrape::{HeadwordMatch, ScrapeError};
+use sources::{SourceRegistry, SourceResult};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct Definition {
     pub(crate) part_of_speech: String,
     pub(crate) meaning: String,
@@ -28,100 +50,434 @@ impl Display for Definition {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct Origin {
     pub(crate) part_of_speech: String,
     pub(crate) origin: String,
 }
 
-#[derive(Serialize, Deserialize)]
-pub(crate) struct Word {
-    pub(crate) overview: Vec<String>,
-    pub(crate) vocab_defs: Vec<Definition>,
-    pub(crate) macmillan_defs: Vec<Vec<Definition>>,
-    pub(crate) wiki_defs: Vec<Definition>,
+/// The definitions from one source, which get a tab of their own.
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct Dictionary {
+    pub(crate) source: String,
+    pub(crate) name: String,
+    pub(crate) definitions: Vec<Vec<Definition>>,
+}
 
-    pub(crate) etym_origins: Vec<Origin>,
-    pub(crate) wiki_origins: Vec<Origin>,
+/// The word the sources filed a lookup under, when it isn't the one asked for.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Redirect {
+    /// The word that was asked for.
+    pub(crate) from: String,
+    pub(crate) how: HeadwordMatch,
+}
 
-    pub(crate) stock_images: Vec<String>,
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct Word {
+    /// The word as the sources spell it.
+    #[serde(default)]
+    pub(crate) headword: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) redirect: Option<Redirect>,
+    pub(crate) overview: Vec<String>,
+    pub(crate) dictionaries: Vec<Dictionary>,
+    pub(crate) origins: Vec<Origin>,
+    pub(crate) media: Vec<String>,
 
     pub(crate) sources: Vec<String>,
+    /// Why each source that failed did, by id.
+    pub(crate) errors: BTreeMap<String, String>,
+    /// The ids of the sources that didn't answer in time.
+    pub(crate) timed_out: Vec<String>,
+    /// The ids of the sources still being asked when this was put together.
+    /// Only partial answers have any, and they're never cached.
+    #[serde(default)]
+    pub(crate) pending: Vec<String>,
+    /// Whether this is an old entry that's being scraped again, given
+    /// because the new one wasn't ready in time. These aren't cached either.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) refreshing: bool,
 
     pub(crate) last_updated: String,
-    version_0_1_2: String,
+    version_0_2_0: String,
 }
 
 impl Word {
-    pub(crate) async fn scrape(word: &str) -> Option<Self> {
-        let mut sources = Vec::new();
+    /// How long ago this word was scraped, if `last_updated` can be read.
+    pub(crate) fn age(&self) -> Option<Duration> {
+        let scraped = Duration::from_millis(self.last_updated.parse().ok()?);
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?;
+        Some(now.saturating_sub(scraped))
+    }
 
-        let (short, long, vocab_defs, source) =
-            scrape_vocab(&word)
-                .await
-                .unwrap_or((None, None, Vec::new(), ""));
+    /// Asks every source about `word` at once, reporting on each as soon as
+    /// it's done. A source that takes longer than its timeout, or runs past
+    /// the registry's deadline, is reported as timed out. Only the sources
+    /// that fail or run past their own timeout count against their breaker.
+    /// Sources whose breaker is open aren't asked, and neither are the ones
+    /// in `known_misses`, which are reported missing for the reason given.
+    pub(crate) fn scrape_each<'a>(
+        word: &'a str,
+        sources: &'a SourceRegistry,
+        known_misses: &'a HashMap<&'static str, String>,
+    ) -> impl Stream<Item = SourceReport> + 'a {
+        let deadline = Instant::now() + sources.deadline;
 
-        let mut overview = Vec::new();
-        if let Some(short) = short {
-            overview.push(short)
-        }
-        if let Some(long) = long {
-            overview.push(long)
-        }
-        if !source.is_empty() {
-            sources.push(source.to_string())
-        }
+        sources
+            .iter()
+            .map(|source| async move {
+                let id = source.id();
+                let report = |outcome| SourceReport {
+                    source: id,
+                    name: source.name(),
+                    outcome,
+                };
 
-        let (macmillan_defs, source) = scrape_macmillan(&word).await.unwrap_or((Vec::new(), ""));
+                if let Some(reason) = known_misses.get(id) {
+                    return report(Outcome::Missing(reason.clone()));
+                }
 
-        if !source.is_empty() {
-            sources.push(source.to_string())
-        }
+                let breaker = sources.breaker(source.as_ref());
+                if !breaker.allow() {
+                    return report(Outcome::Failed(ScrapeError::CircuitOpen.to_string()));
+                }
+
+                let own_limit = Instant::now() + sources.timeout(source.as_ref());
+                let result = timeout_at(
+                    deadline.min(own_limit),
+                    source.scrape(&sources.http, sources.upstream(source.as_ref()), word),
+                )
+                .await;
 
-        let (wiki_origins, wiki_defs, source) =
-            scrape_wiki(&word)
-                .await
-                .unwrap_or((Vec::new(), Vec::new(), ""));
+                // not having the word is the source working fine
+                let failure = match &result {
+                    Ok(Ok(_)) => None,
+                    Ok(Err(err)) if err.is_miss() => None,
+                    Ok(Err(err)) => Some(err.to_string()),
+                    Err(_) => Some("took too long".to_string()),
+                };
+                // and being cut off by the lookup's deadline says nothing
+                // about it either way
+                let cut_off = result.is_err() && deadline < own_limit;
+                match failure {
+                    _ if cut_off => {}
+                    None => {
+                        if breaker.succeed() {
+                            info!("`{}` is answering again", id);
+                        }
+                    }
+                    Some(err) => {
+                        if breaker.fail(err) {
+                            warn!(
+                                "`{}` keeps failing, so it's being left alone for a while",
+                                id
+                            );
+                        }
+                    }
+                }
 
-        if !source.is_empty() {
-            sources.push(source.to_string())
+                report(match result {
+                    Ok(Ok(result)) => Outcome::Found(result),
+                    Ok(Err(err)) if err.is_miss() => {
+                        info!("`{}` doesn't have `{}`: {}", id, word, err);
+                        Outcome::Missing(err.to_string())
+                    }
+                    Ok(Err(err)) => {
+                        warn!("`{}` couldn't look up `{}`: {}", id, word, err);
+                        Outcome::Failed(err.to_string())
+                    }
+                    Err(_) => {
+                        warn!("`{}` took too long to look up `{}`", id, word);
+                        Outcome::TimedOut
+                    }
+                })
+            })
+            .collect::<FuturesUnordered<_>>()
+    }
+
+    /// Puts the sources' reports on `word` together in registry order, or
+    /// returns `None` if none of them found a definition. Sources without a
+    /// report yet are marked pending. The first source to spell the word
+    /// differently decides its headword.
+    pub(crate) fn assemble(
+        word: &str,
+        reports: Vec<SourceReport>,
+        sources: &SourceRegistry,
+    ) -> Option<Self> {
+        let mut results = HashMap::new();
+        let mut errors = BTreeMap::new();
+        let mut timed_out = Vec::new();
+        let answered: Vec<_> = reports.iter().map(|report| report.source).collect();
+        for report in reports {
+            match report.outcome {
+                Outcome::Found(result) => {
+                    results.insert(report.source, result);
+                }
+                Outcome::Missing(err) | Outcome::Failed(err) => {
+                    errors.insert(report.source.to_string(), err);
+                }
+                Outcome::TimedOut => timed_out.push(report.source),
+            }
         }
 
         // check for no defs
-        if vocab_defs.is_empty() && macmillan_defs.is_empty() && wiki_defs.is_empty() {
+        if results.values().all(|result| result.definitions.is_empty()) {
             return None;
         }
 
-        let (etym_origins, source) = scrape_etym(&word).await.unwrap_or((Vec::new(), ""));
+        let now = SystemTime::now();
+        let now = now.duration_since(UNIX_EPOCH).expect("Time went backwards");
 
-        if !source.is_empty() {
-            sources.push(source.to_string())
+        let asked = word;
+        let mut word = Word {
+            headword: asked.to_string(),
+            redirect: None,
+            overview: Vec::new(),
+            dictionaries: Vec::new(),
+            origins: Vec::new(),
+            media: Vec::new(),
+
+            sources: Vec::new(),
+            errors,
+            timed_out: Vec::new(),
+            pending: Vec::new(),
+            refreshing: false,
+
+            last_updated: now.as_millis().to_string(),
+            version_0_2_0: String::new(),
+        };
+
+        for source in sources.iter() {
+            if timed_out.contains(&source.id()) {
+                word.timed_out.push(source.id().to_string());
+            }
+            if !answered.contains(&source.id()) {
+                word.pending.push(source.id().to_string());
+            }
+            let Some(result) = results.remove(source.id()) else {
+                continue;
+            };
+            let is_empty = result.is_empty();
+            let SourceResult {
+                headword,
+                overview,
+                definitions,
+                origins,
+                media,
+                attribution,
+            } = result;
+
+            if let Some(headword) = headword.filter(|_| word.redirect.is_none()) {
+                word.redirect = Some(Redirect {
+                    from: asked.to_string(),
+                    how: HeadwordMatch::between(asked, &headword)
+                        .unwrap_or(HeadwordMatch::Redirect),
+                });
+                word.headword = headword;
+            }
+            word.overview.extend(overview);
+            if !definitions.is_empty() {
+                word.dictionaries.push(Dictionary {
+                    source: source.id().to_string(),
+                    name: source.name().to_string(),
+                    definitions,
+                });
+            }
+            if word.origins.is_empty() {
+                word.origins = origins;
+            }
+            word.media.extend(media);
+            if !is_empty {
+                word.sources.push(attribution);
+            }
         }
 
-        let (stock_images, source) = scrape_stock(&word).await.unwrap_or((Vec::new(), ""));
+        Some(word)
+    }
+}
+
+/// How one source answered a lookup.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct SourceReport {
+    pub(crate) source: &'static str,
+    pub(crate) name: &'static str,
+    #[serde(flatten)]
+    pub(crate) outcome: Outcome,
+}
 
-        if !source.is_empty() {
-            sources.push(source.to_string())
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "outcome", content = "detail", rename_all = "snake_case")]
+pub(crate) enum Outcome {
+    Found(SourceResult),
+    /// Why the source doesn't have the word.
+    Missing(String),
+    /// Why the source couldn't say anything.
+    Failed(String),
+    TimedOut,
+}
+
+impl Outcome {
+    /// Whether the source gave an answer that's worth remembering, even if
+    /// the answer was that it doesn't know the word.
+    pub(crate) fn is_answer(&self) -> bool {
+        matches!(self, Outcome::Found(_) | Outcome::Missing(_))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, sync::Arc};
+
+    use rocket::futures::StreamExt;
+
+    use super::{
+        http::{Http, HttpConfig, HttpMode},
+        sources::{
+            fixtures::{defined_as, Stub},
+            DictionarySource, ScrapeConfig,
+        },
+        *,
+    };
+
+    async fn scrape(word: &str, sources: &SourceRegistry) -> Option<Word> {
+        let reports = Word::scrape_each(word, sources, &HashMap::new())
+            .collect()
+            .await;
+        Word::assemble(word, reports, sources)
+    }
+
+    /// What `fake` sources answer with.
+    fn found(id: &'static str) -> SourceResult {
+        SourceResult {
+            attribution: id.to_string(),
+            ..defined_as(format!("a word according to {}", id))
         }
+    }
 
-        let now = SystemTime::now();
-        let now = now.duration_since(UNIX_EPOCH).expect("Time went backwards");
+    fn fake(id: &'static str, delay_ms: u64) -> Arc<dyn DictionarySource> {
+        Arc::new(Stub::new(id, move |_| Ok(found(id))).delayed(delay_ms))
+    }
 
-        Some(Word {
-            overview,
-            vocab_defs,
-            macmillan_defs,
-            wiki_defs,
+    #[rocket::async_test]
+    async fn slow_sources_time_out() {
+        let config = ScrapeConfig {
+            timeout_ms: 100,
+            timeouts: HashMap::from([("patient".to_string(), 1_000)]),
+            deadline_ms: 300,
+            ..Default::default()
+        };
+        let sources = SourceRegistry::new(
+            vec![
+                fake("fast", 0),
+                fake("slow", 200),
+                fake("patient", 200),
+                fake("hopeless", 500),
+            ],
+            Arc::default(),
+            &config,
+        );
 
-            wiki_origins,
-            etym_origins,
+        let word = scrape("word", &sources).await.unwrap();
 
-            stock_images,
+        let found: Vec<_> = word.dictionaries.iter().map(|dict| &dict.source).collect();
+        assert_eq!(found, ["fast", "patient"]);
+        assert_eq!(word.timed_out, ["slow", "hopeless"]);
+    }
 
-            sources,
+    #[rocket::async_test]
+    async fn only_broken_sources_trip_their_breaker() {
+        let config = ScrapeConfig {
+            timeout_ms: 1_000,
+            timeouts: HashMap::from([("slow".to_string(), 50)]),
+            deadline_ms: 100,
+            breaker: BreakerConfig {
+                failures: 1,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let sources = SourceRegistry::new(
+            vec![
+                Arc::new(Stub::new("typo", |_| Err(ScrapeError::NotFound))),
+                Arc::new(Stub::new("broken", |_| {
+                    Err(ScrapeError::MissingSelector(".definitions"))
+                })),
+                // would have answered, if the lookup had waited for it
+                fake("cut_off", 200),
+                fake("slow", 200),
+            ],
+            Arc::default(),
+            &config,
+        );
 
-            last_updated: now.as_millis().to_string(),
-            version_0_1_2: String::new(),
+        assert!(scrape("dgo", &sources).await.is_none());
+
+        let state = |id| {
+            let source = sources.iter().find(|source| source.id() == id).unwrap();
+            sources.breaker(source.as_ref()).status().state
+        };
+        assert_eq!(state("typo"), BreakerState::Closed);
+        assert_eq!(state("broken"), BreakerState::Open);
+        assert_eq!(state("cut_off"), BreakerState::Closed);
+        assert_eq!(state("slow"), BreakerState::Open);
+    }
+
+    #[test]
+    fn the_first_source_to_respell_the_word_names_it() {
+        let sources = SourceRegistry::new(
+            vec![fake("first", 0), fake("second", 0), fake("third", 0)],
+            Arc::default(),
+            &ScrapeConfig::default(),
+        );
+        let report = |source, headword: Option<&str>| SourceReport {
+            source,
+            name: source,
+            outcome: Outcome::Found(SourceResult {
+                headword: headword.map(str::to_string),
+                ..found(source)
+            }),
+        };
+
+        let word = Word::assemble(
+            "monday",
+            vec![
+                report("third", Some("Mon.")),
+                report("second", Some("Monday")),
+                report("first", None),
+            ],
+            &sources,
+        )
+        .unwrap();
+        assert_eq!(word.headword, "Monday");
+        let redirect = word.redirect.unwrap();
+        assert_eq!(redirect.from, "monday");
+        assert_eq!(redirect.how, HeadwordMatch::Case);
+
+        let word = Word::assemble("dog", vec![report("first", None)], &sources).unwrap();
+        assert_eq!(word.headword, "dog");
+        assert!(word.redirect.is_none());
+    }
+
+    #[rocket::async_test]
+    async fn replays_recorded_lookup() {
+        let http = Http::new(&HttpConfig {
+            mode: HttpMode::Replay,
+            dir: PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/recordings"),
+            ..Default::default()
         })
+        .unwrap();
+        let sources = SourceRegistry::all(Arc::new(http), &ScrapeConfig::default());
+
+        let word = scrape("dog", &sources).await.unwrap();
+
+        let found: Vec<_> = word.dictionaries.iter().map(|dict| &dict.source).collect();
+        assert_eq!(found, ["vocab", "wiki"]);
+        // etymonline comes first, so its two origins win over wiktionary's one
+        assert_eq!(word.origins.len(), 2);
+        assert_eq!(word.media.len(), 6);
+        assert_eq!(word.overview.len(), 2);
+        // macmillan was recorded answering with a 404
+        assert_eq!(word.errors.keys().collect::<Vec<_>>(), ["macmillan"]);
+        assert!(word.timed_out.is_empty());
     }
 }
@@ -0,0 +1,216 @@
+use std::{
+    future::Future,
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+use rand::Rng;
+use serde::Deserialize;
+use tokio::{sync::Semaphore, time::Instant};
+
+/// How gently to treat a source's site, so a busy day here doesn't get us
+/// blocked there.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub(crate) struct Politeness {
+    /// Requests a second, on average. Zero or less means no limit.
+    pub(crate) rate: f64,
+    /// How many requests can go out back to back before `rate` kicks in.
+    pub(crate) burst: u32,
+    /// Requests in flight at once.
+    pub(crate) max_concurrent: usize,
+    /// How long a single attempt can take before it counts as timed out.
+    pub(crate) attempt_timeout_ms: u64,
+    /// How many times to retry a 5xx, a 429 or a timeout.
+    pub(crate) retries: u32,
+    /// The wait before the first retry, doubling after that.
+    pub(crate) backoff_ms: u64,
+    /// The longest wait before a retry. A `Retry-After` asking for longer
+    /// than this is given up on instead.
+    pub(crate) max_backoff_ms: u64,
+}
+
+impl Default for Politeness {
+    fn default() -> Self {
+        Self {
+            rate: 2.0,
+            burst: 5,
+            max_concurrent: 4,
+            attempt_timeout_ms: 5_000,
+            retries: 2,
+            backoff_ms: 250,
+            max_backoff_ms: 2_000,
+        }
+    }
+}
+
+impl Politeness {
+    pub(crate) fn attempt_timeout(&self) -> Duration {
+        Duration::from_millis(self.attempt_timeout_ms)
+    }
+
+    /// How long to wait before retrying after attempt number `attempt`
+    /// (counting from 0) failed, or `None` to give up. `retry_after` is what
+    /// the site asked for, if anything.
+    pub(crate) fn retry_delay(
+        &self,
+        attempt: u32,
+        retry_after: Option<Duration>,
+    ) -> Option<Duration> {
+        if attempt >= self.retries {
+            return None;
+        }
+
+        let max = Duration::from_millis(self.max_backoff_ms);
+        if let Some(retry_after) = retry_after {
+            return (retry_after <= max).then_some(retry_after);
+        }
+
+        // exponential, with jitter so that lookups that failed together
+        // don't all come back together
+        let backoff = Duration::from_millis(self.backoff_ms)
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(max);
+        Some(backoff.mul_f64(rand::thread_rng().gen_range(0.5..=1.0)))
+    }
+}
+
+/// Reads a `Retry-After` header, which is either a number of seconds or a date.
+pub(crate) fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(date.duration_since(now).unwrap_or(Duration::ZERO))
+}
+
+/// The limits for one host, shared by every request to it.
+pub(crate) struct HostLimiter {
+    permits: Semaphore,
+    rate: f64,
+    burst: f64,
+    bucket: Mutex<Bucket>,
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl HostLimiter {
+    pub(crate) fn new(politeness: &Politeness) -> Self {
+        let burst = f64::from(politeness.burst.max(1));
+        Self {
+            permits: Semaphore::new(politeness.max_concurrent.max(1)),
+            rate: politeness.rate,
+            burst,
+            bucket: Mutex::new(Bucket {
+                tokens: burst,
+                refilled: Instant::now(),
+            }),
+        }
+    }
+
+    /// Waits for a free slot and a turn under the rate limit, then runs `request`.
+    pub(crate) async fn run<F: Future>(&self, request: F) -> F::Output {
+        let _permit = self
+            .permits
+            .acquire()
+            .await
+            .expect("the semaphore is never closed");
+        tokio::time::sleep(self.reserve()).await;
+        request.await
+    }
+
+    /// Takes a token, returning how long to wait until it's actually there.
+    /// Tokens can go negative, which queues requests up in the order they
+    /// arrived.
+    fn reserve(&self) -> Duration {
+        if self.rate <= 0.0 {
+            return Duration::ZERO;
+        }
+
+        let mut bucket = self.bucket.lock().unwrap();
+        let now = Instant::now();
+        let refill = now.duration_since(bucket.refilled).as_secs_f64() * self.rate;
+        bucket.tokens = (bucket.tokens + refill).min(self.burst);
+        bucket.refilled = now;
+
+        bucket.tokens -= 1.0;
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / self.rate)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_with_jitter_up_to_the_max() {
+        let politeness = Politeness {
+            retries: 4,
+            backoff_ms: 100,
+            max_backoff_ms: 300,
+            ..Default::default()
+        };
+
+        let ms = |attempt| politeness.retry_delay(attempt, None).unwrap().as_millis();
+        assert!((50..=100).contains(&ms(0)));
+        assert!((100..=200).contains(&ms(1)));
+        assert!((150..=300).contains(&ms(2)));
+        assert!((150..=300).contains(&ms(3)));
+        assert_eq!(politeness.retry_delay(4, None), None);
+    }
+
+    #[test]
+    fn retry_after_is_honored_unless_too_long() {
+        let politeness = Politeness::default();
+
+        assert_eq!(
+            politeness.retry_delay(0, Some(Duration::from_secs(1))),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(
+            politeness.retry_delay(0, Some(Duration::from_secs(60))),
+            None
+        );
+    }
+
+    #[test]
+    fn retry_after_is_seconds_or_a_date() {
+        let now = httpdate::parse_http_date("Wed, 21 Oct 2015 07:28:00 GMT").unwrap();
+
+        assert_eq!(
+            parse_retry_after("120", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:27:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[test]
+    fn requests_past_the_burst_are_spaced_out() {
+        let limiter = HostLimiter::new(&Politeness {
+            rate: 10.0,
+            burst: 2,
+            ..Default::default()
+        });
+
+        let waits: Vec<_> = (0..4).map(|_| limiter.reserve().as_millis()).collect();
+        assert_eq!(waits[..2], [0, 0]);
+        assert!((90..=100).contains(&waits[2]));
+        assert!((190..=200).contains(&waits[3]));
+    }
+}
@@ -0,0 +1,128 @@
+use std::fmt::Display;
+
+use rocket::request::FromParam;
+
+/// The most characters a query can have. The longest words in English are
+/// a bit over 40 letters, and phrases don't go on much longer.
+pub(crate) const MAX_CHARS: usize = 64;
+/// The most words a phrase can have.
+pub(crate) const MAX_WORDS: usize = 6;
+
+/// Why a query can't be looked up.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Rejection {
+    Empty,
+    TooLong,
+    TooManyWords,
+    NoLetters,
+    /// The first character that can't be in a word.
+    Character(char),
+}
+
+impl Display for Rejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rejection::Empty => write!(f, "there's no word"),
+            Rejection::TooLong => write!(f, "longer than {} characters", MAX_CHARS),
+            Rejection::TooManyWords => write!(f, "more than {} words", MAX_WORDS),
+            Rejection::NoLetters => write!(f, "there are no letters in it"),
+            Rejection::Character(c) => write!(f, "{:?} can't be in a word", c),
+        }
+    }
+}
+
+/// A word or phrase that's worth asking the sources about: letters and
+/// digits, with apostrophes, hyphens and dots in between, and single spaces
+/// between the words of a phrase. Checking this first means junk is turned
+/// away before anything is fetched, and nothing that means something in a
+/// url ever makes it into one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Query(String);
+
+impl Query {
+    pub(crate) fn parse(raw: &str) -> Result<Self, Rejection> {
+        // checked before anything else, so a huge query costs nothing
+        if raw.len() > MAX_CHARS * 4 {
+            return Err(Rejection::TooLong);
+        }
+
+        let words: Vec<_> = raw.split_whitespace().collect();
+        if words.is_empty() {
+            return Err(Rejection::Empty);
+        }
+        if words.len() > MAX_WORDS {
+            return Err(Rejection::TooManyWords);
+        }
+
+        let query = words.join(" ");
+        if query.chars().count() > MAX_CHARS {
+            return Err(Rejection::TooLong);
+        }
+        if let Some(c) = query.chars().find(|&c| !allowed(c)) {
+            return Err(Rejection::Character(c));
+        }
+        if !query.chars().any(char::is_alphabetic) {
+            return Err(Rejection::NoLetters);
+        }
+
+        Ok(Self(query))
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+fn allowed(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, ' ' | '\'' | '’' | '-' | '.')
+}
+
+impl Display for Query {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl<'a> FromParam<'a> for Query {
+    type Error = Rejection;
+
+    fn from_param(param: &'a str) -> Result<Self, Self::Error> {
+        Query::parse(param)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn words_and_phrases_are_tidied_up() {
+        let ok = |raw| Query::parse(raw).unwrap().to_string();
+
+        assert_eq!(ok("dog"), "dog");
+        assert_eq!(ok("  kick the\tbucket "), "kick the bucket");
+        assert_eq!(ok("rock 'n' roll"), "rock 'n' roll");
+        assert_eq!(ok("e.g."), "e.g.");
+        assert_eq!(ok("naïve"), "naïve");
+        assert_eq!(ok("3D"), "3D");
+    }
+
+    #[test]
+    fn junk_is_rejected_with_a_reason() {
+        let err = |raw: &str| Query::parse(raw).unwrap_err();
+
+        assert_eq!(err(" \t "), Rejection::Empty);
+        assert_eq!(err(&"a".repeat(MAX_CHARS + 1)), Rejection::TooLong);
+        assert_eq!(err(&"ab".repeat(5_000)), Rejection::TooLong);
+        assert_eq!(err("a b c d e f g"), Rejection::TooManyWords);
+        assert_eq!(err("123"), Rejection::NoLetters);
+        assert_eq!(err("dog/../cat"), Rejection::Character('/'));
+        assert_eq!(err("dog?x=1"), Rejection::Character('?'));
+        assert_eq!(err("dog#top"), Rejection::Character('#'));
+        assert_eq!(err("cats&dogs"), Rejection::Character('&'));
+        assert_eq!(
+            Rejection::Character('&').to_string(),
+            "'&' can't be in a word"
+        );
+    }
+}
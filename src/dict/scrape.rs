@@ -1,430 +1,177 @@
+use std::fmt::Display;
 
 use ego_tree::NodeRef;
-use regex::Regex;
-use scraper::{Html, Selector, ElementRef, Node, node::Text};
-
-use super::{Origin, Definition, restrictor::RESTRICTOR};
+use reqwest::StatusCode;
+use scraper::Node;
+use serde::{Deserialize, Serialize};
 
 macro_rules! find {
-    ($parent: expr, $selector: literal) => {
-        {
-            let sel = Selector::parse($selector).unwrap();
-            $parent.select(&sel).nth(0)
-        }
-    };
+    ($parent: expr, $selector: literal) => {{
+        let sel = ::scraper::Selector::parse($selector).unwrap();
+        $parent.select(&sel).nth(0)
+    }};
 }
 
 macro_rules! find_loop {
-    ($parent: expr, $selector: literal, $name: ident, $for_each: expr) => {
-        {
-            let sel = Selector::parse($selector).unwrap();
-            for $name in $parent.select(&sel) {
-                $for_each
-            }
+    ($parent: expr, $selector: literal, $name: ident, $for_each: expr) => {{
+        let sel = ::scraper::Selector::parse($selector).unwrap();
+        for $name in $parent.select(&sel) {
+            $for_each
         }
-    };
+    }};
 }
 
-const PROTOCOL: &str = "https";
-const VOCAB_URL_BASE: &str = "www.vocabulary.com";
-const MACMILLAN_URL_BASE: &str = "www.macmillandictionary.com";
-const WIKI_URL_BASE: &str = "en.wiktionary.org";
-const ETYM_URL_BASE: &str = "www.etymonline.com";
-const STOCK_URL_BASE: &str = "stock.adobe.com";
-
-pub(crate) async fn scrape_vocab(word: &str) -> Option<(Option<String>, Option<String>, Vec<Definition>, &str)> {
-    let body = reqwest::get(&format!(
-        "{}://{}/dictionary/definition.ajax?search={}&lang=en",
-        PROTOCOL,
-        VOCAB_URL_BASE,
-        word,
-    ))
-        .await
-        .ok()?
-        .text()
-        .await
-        .ok()?;
-
-    let doc = Html::parse_document(&body);
-
-    let word_area = find!(doc.root_element(), ".word-area")?;
-
-    let real_word = el_to_string(*find!(word_area, "h1")?);
-    if real_word != word {
-        return None;
-    }
-
-    let short_overview = Some(el_to_string_with(*find!(word_area, ".short")?, &[], true, &[INCLUDED_TAGS, &["i"]].concat()))
-        .filter(|s| !s.is_empty());
-    let long_overview = Some(el_to_string_with(*find!(word_area, ".long")?, &[], true, &[INCLUDED_TAGS, &["i"]].concat()))
-        .filter(|s| !s.is_empty());
-
-    let ol = find!(find!(doc, ".word-definitions")?, "ol")?;
-
-    let mut definitions = Vec::new();
-
-    find_loop!(ol, "li", item, {
-        let def_area = find!(item, ".definition")?;
-
-        let part_of_speech = el_to_string(*find!(def_area, ".pos-icon")?);
-
-        let meaning = el_to_string(*def_area);
-
-        let mut examples = Vec::new();
-
-        find_loop!(item, ".example", example, {
-            examples.push(el_to_string(*example).replace("\n", ""));
-        });
-
-        definitions.push(Definition {
-            part_of_speech,
-            meaning,
-            examples,
-        });
-    });
-
-    Some((short_overview, long_overview, definitions, VOCAB_URL_BASE))
+/// Like `find!`, but a missing element is a `ScrapeError` naming the selector.
+macro_rules! require {
+    ($parent: expr, $selector: literal) => {
+        find!($parent, $selector).ok_or($crate::dict::scrape::ScrapeError::MissingSelector(
+            $selector,
+        ))
+    };
 }
 
-pub(crate) async fn scrape_macmillan(word: &str) -> Option<(Vec<Vec<Definition>>, &str)> {
-    let body = reqwest::get(&format!(
-        "{}://{}/us/dictionary/american/{}",
-        PROTOCOL,
-        MACMILLAN_URL_BASE,
-        word,
-    ))
-        .await
-        .ok()?
-        .text()
-        .await
-        .ok()?;
-
-    let doc = Html::parse_document(&body);
-
-    let word_area = find!(doc, ".left-content")?;
-
-    let real_word = el_to_string(word_area
-        .first_child()?
-        .first_child()?
-        .first_child()?
-        .first_child()?);
-    if real_word != word {
-        return None;
-    }
-
-    let part_of_speech = el_to_string(*find!(word_area, ".PART-OF-SPEECH").unwrap());
-
-    let definition_area = word_area.children()
-        .find(|child| child.value().is_element() 
-            && child.value().as_element().unwrap().attrs.is_empty())?
-        .children()
-        .find(|child| child.value().is_element() 
-            && child.value().as_element().unwrap().attrs.is_empty())?;
-    
-    let ol = find!(ElementRef::wrap(definition_area).unwrap(), "ol")?;
-
-    let mut definitions = Vec::new();
-
-    find_loop!(ol, "li", item, {
-        let sense_body = if let Some(el) = find!(item, ".SENSE-BODY") {
-            el
-        } else {
-            continue;
-        };
-
-        let mut sense = Vec::new();
-
-        find_loop!(sense_body, ".dflex", body, {
-            let meaning = if let Some(el) = find!(body, ".DEFINITION") {
-                el
-            } else {
-                continue;
-            };
-
-            let meaning = el_to_string_with(*meaning, &["a", "span"], false, INCLUDED_TAGS);
-
-            let mut examples = Vec::new();
-            if let Some(examples_el) = find!(body, ".EXAMPLES") {
-                for example in examples_el.children() {
-                    if !example.value().is_element() || example.value().as_element().unwrap().name() != "p" {
-                        continue;
-                    }
-                    examples.push(el_to_string_with(example, &["a", "span"], false, INCLUDED_TAGS).replace("\n", ""));
-                }
-            }
-
-            sense.push(Definition {
-                part_of_speech: part_of_speech.clone(),
-                meaning,
-                examples,
-            });
-        });
-
-        definitions.push(sense);
-    });
-
-    Some((definitions, MACMILLAN_URL_BASE))
+pub(crate) use find;
+pub(crate) use find_loop;
+pub(crate) use require;
+
+pub(crate) const PROTOCOL: &str = "https";
+
+/// Why a source couldn't say anything about a word.
+#[derive(Debug)]
+pub(crate) enum ScrapeError {
+    /// The page couldn't be downloaded at all.
+    Fetch(reqwest::Error),
+    /// The page came back, but not with a 2xx.
+    Status(StatusCode),
+    /// Something the parser relies on isn't on the page, which usually means
+    /// the layout changed.
+    MissingSelector(&'static str),
+    /// The source answered with the page it shows for words it doesn't have.
+    NotFound,
+    /// The source showed a different word than the one asked for.
+    WordMismatch { expected: String, found: String },
+    /// The word is one we don't look up.
+    Restricted,
+    /// Replaying, and nothing was recorded for the url.
+    NotRecorded(String),
+    /// The source kept failing, so it wasn't asked.
+    CircuitOpen,
 }
 
-pub(crate) async fn scrape_wiki(word: &str) -> Option<(Vec<Origin>, Vec<Definition>, &str)> {
-    let body = reqwest::get(&format!(
-        "{}://{}/wiki/{}",
-        PROTOCOL,
-        WIKI_URL_BASE,
-        word,
-    ))
-        .await
-        .ok()?
-        .text()
-        .await
-        .ok()?;
-
-    let doc = Html::parse_document(&body);
-
-    let eng = find!(doc, "#English")?;
-
-    let mut origins = Vec::new();
-    let mut working_origin_p = String::new();
-
-    let mut definitions = Vec::new();
-
-    let mut last_origin_title = String::new();
-    let mut first_def_title = String::new();
-    let mut last_title = String::new();
-
-    let mut got_to_eng = false;
-    for child in eng.parent().unwrap().parent().unwrap().children() {
-        if !child.value().is_element() { continue }
-        let el = child.value().as_element().unwrap();
-        let el_ref = ElementRef::wrap(child).unwrap();
-
-        if !got_to_eng {
-            got_to_eng = find!(el_ref, "#English").is_some();
-            continue;
-        }
-
-        match el.name() {
-            "h3" | "h4" | "h5" => {
-                let title = find!(el_ref, ".mw-headline")?;
-                last_title = el_to_string(*title).to_lowercase();
-            },
-            "ol" => {
-                for grandchild in child.children() {
-                    if !grandchild.value().is_element() { continue }
-
-                    let meaning = el_to_string_with(grandchild, &["a"], false, &[INCLUDED_TAGS, &["span", "i"]].concat());
-                    if meaning.is_empty() { continue }
-
-                    let mut examples = Vec::new();
-
-                    let examples_list = find!(ElementRef::wrap(grandchild).unwrap(), "dl");
-                    if examples_list.is_some() {
-                        for el in examples_list.unwrap().children() {
-                            if !el.value().is_element() || el.value().as_element().unwrap().name() != "dd" { continue }
-                            examples.push(el_to_string_with(el, &["span", "i"], true, INCLUDED_TAGS))
-                        }
-                    }
-
-                    definitions.push(Definition {
-                        part_of_speech: match last_title.as_str() {
-                            "numeral" | "number" | "letter" => "noun",
-                            text => text,
-                        }.to_owned(),
-                        meaning,
-                        examples,
-                    });
-
-                    if first_def_title.is_empty() {
-                        first_def_title = last_title.clone();
-                    }
-                }
-            },
-            "hr" => break,
-            "p" if last_title.starts_with("etymology") => {
-                if !working_origin_p.is_empty() 
-                    && (origins.is_empty() || last_title != last_origin_title) 
-                    && !first_def_title.is_empty() {
-                    origins.push(Origin {
-                        part_of_speech: first_def_title.clone(),
-                        origin: working_origin_p.clone(),
-                    });
-                    working_origin_p.clear();
-                    last_origin_title = last_title.clone();
-                }
-
-                // another paragraph to the working origin
-
-                if !working_origin_p.is_empty() {
-                    working_origin_p.push_str("<br>");
-                }
-
-                working_origin_p.push_str(&el_to_string_with(child, &["span"], false, INCLUDED_TAGS));
-
-                first_def_title.clear();
+impl ScrapeError {
+    /// Whether the source is working fine and just doesn't have the word.
+    pub(crate) fn is_miss(&self) -> bool {
+        match self {
+            ScrapeError::Status(status) => *status == StatusCode::NOT_FOUND,
+            ScrapeError::NotFound | ScrapeError::WordMismatch { .. } | ScrapeError::Restricted => {
+                true
             }
-            _ => {},
+            ScrapeError::Fetch(_)
+            | ScrapeError::MissingSelector(_)
+            | ScrapeError::NotRecorded(_)
+            | ScrapeError::CircuitOpen => false,
         }
     }
-
-    if !working_origin_p.is_empty() {
-        origins.push(Origin {
-            part_of_speech: first_def_title.clone(),
-            origin: working_origin_p,
-        });
-    }
-
-    Some((origins, definitions, WIKI_URL_BASE))
 }
 
-pub(crate) async fn scrape_etym(word: &str) -> Option<(Vec<Origin>, &str)> {
-    let body = reqwest::get(&format!(
-        "{}://{}/word/{}",
-        PROTOCOL,
-        ETYM_URL_BASE,
-        word,
-    ))
-        .await
-        .ok()?
-        .text()
-        .await
-        .ok()?;
-
-    let doc = Html::parse_document(&body);
-
-    fn find_startswith<'a>(node: NodeRef<'a, Node>, pat: &str) -> Option<NodeRef<'a, Node>> {
-        for child in node.children() {
-            if !child.value().is_element() { continue }
-            let el = child.value().as_element().unwrap();
-            match el.attr("class") {
-                Some(class) if class.starts_with(pat) => {
-                    return Some(child);
-                },
-                _ => {
-                    let rec = find_startswith(child, pat);
-                    if rec.is_some() {
-                        return rec;
-                    }
-                },
+impl Display for ScrapeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScrapeError::Fetch(err) => write!(f, "couldn't fetch the page: {}", err),
+            ScrapeError::Status(status) => write!(f, "the page came back with {}", status),
+            ScrapeError::MissingSelector(selector) => {
+                write!(f, "couldn't find `{}` on the page", selector)
+            }
+            ScrapeError::NotFound => write!(f, "the source doesn't have the word"),
+            ScrapeError::WordMismatch { expected, found } => {
+                write!(f, "the page is for `{}`, not `{}`", found, expected)
             }
+            ScrapeError::Restricted => write!(f, "the word is restricted"),
+            ScrapeError::NotRecorded(url) => write!(f, "nothing was recorded for {}", url),
+            ScrapeError::CircuitOpen => write!(f, "skipped, since it keeps failing"),
         }
-        None
     }
+}
 
-    let mut origins = Vec::new();
-
-    find_loop!(doc, ".word--C9UPa", word_entry, {
-        let word_name = find_startswith(*word_entry, "word__name")?;
-
-        let word_name_text: Vec<&Text> = word_name
-            .children()
-            .filter_map(|child| match child.value() {
-                Node::Text(text) => Some(text),
-                _ => None,
-            })
-            .collect();
-        
-        let real_word_name = word_name_text
-            .iter()
-            .next()?
-            .to_string();
+/// How the headword on a page compares to the word that was asked for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum HeadwordMatch {
+    Exact,
+    /// The same apart from capitals, like "monday" and "Monday".
+    Case,
+    /// Spelled a little differently, like "cafe" and "café" or "email" and "e-mail".
+    Variant,
+    /// The site sent us on from the word's page to another word's.
+    Redirect,
+    /// The word is an inflected form of the headword, like "running" of "run".
+    Inflection,
+}
 
-        if real_word_name != word {
-            break;
+impl HeadwordMatch {
+    /// Compares the headword `found` on a page with the word `asked` for, or
+    /// returns `None` if they're different words as far as it can tell.
+    pub(crate) fn between(asked: &str, found: &str) -> Option<Self> {
+        if asked == found {
+            Some(HeadwordMatch::Exact)
+        } else if asked.to_lowercase() == found.to_lowercase() {
+            Some(HeadwordMatch::Case)
+        } else if fold(asked) == fold(found) {
+            Some(HeadwordMatch::Variant)
+        } else {
+            None
         }
-        
-        let re = Regex::new(r"[0-9]+").unwrap();
-        let part_of_speech = match re.replace_all(word_name_text.iter().last()?, "")
-            .to_string().as_str() {
-            "(n.)" => "noun",
-            "(v.)" => "verb",
-            "(adj.)" => "adjective",
-            "(adv.)" => "adverb",
-            "(interj.)" => "interjection",
-            "(prep.)" => "preposition",
-            "(pron.)" => "pronoun",
-            text if text.trim().is_empty() => "",
-            text => {
-                println!("NOT YET IMPLEMENTED: `{}` ({:?})", text, text.as_bytes());
-                text
-            }
-        }.to_string();
-
-        let mut origin = String::new();
-
-        find_loop!(find!(ElementRef::wrap(word_name.parent()?)?, "section")?, "p", p, {
-            if !origin.is_empty() {
-                origin.push_str("<br>");
-            }
-            origin.push_str(&&el_to_string_with(*p, &["span", "a"], true, INCLUDED_TAGS));
-        });
-
-        origins.push(Origin {
-            part_of_speech,
-            origin,
-        });
-    });
-
-    if origins.is_empty() {
-        None
-    } else {
-        Some((origins, ETYM_URL_BASE))
     }
 }
 
-pub(crate) async fn scrape_stock(word: &str) -> Option<(Vec<String>, &str)> {
-    if RESTRICTOR.is_restricted(word.to_lowercase().as_str()) { return None }
-
-    let body = reqwest::get(&format!(
-        "{}://{}/search?k={}",
-        PROTOCOL,
-        STOCK_URL_BASE,
-        word,
-    ))
-        .await
-        .ok()?
-        .text()
-        .await
-        .ok()?;
-
-    let doc = Html::parse_document(&body);
-
-    let mut imgs = Vec::new();
-
-    find_loop!(doc, ".search-result-cell", img_div, {
-        let img_el = find!(img_div, "img")?.value();
-
-        if RESTRICTOR.is_restricted(img_el.attr("alt")?.to_lowercase().as_str()) { continue }
-
-        imgs.push(img_el.attr("src")?.to_string());
-
-        if imgs.len() == 6 {
-            break
-        }
-    });
-
-    if imgs.is_empty() {
-        None
-    } else {
-        Some((imgs, STOCK_URL_BASE))
+/// Checks the headword `found` on a page against the word `asked` for,
+/// returning the headword when it's spelled differently but close enough to
+/// be the same word, and a mismatch when it isn't.
+pub(crate) fn check_headword(asked: &str, found: String) -> Result<Option<String>, ScrapeError> {
+    match HeadwordMatch::between(asked, &found) {
+        Some(HeadwordMatch::Exact) => Ok(None),
+        Some(_) => Ok(Some(found)),
+        None => Err(ScrapeError::WordMismatch {
+            expected: asked.to_string(),
+            found,
+        }),
     }
 }
 
+/// Lowercase, without accents and without the punctuation and spaces that
+/// come and go between spellings of the same word.
+fn fold(word: &str) -> String {
+    word.to_lowercase()
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '\'' | '’' | '.'))
+        .map(|c| match c {
+            'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => 'a',
+            'ç' => 'c',
+            'è' | 'é' | 'ê' | 'ë' => 'e',
+            'ì' | 'í' | 'î' | 'ï' => 'i',
+            'ñ' => 'n',
+            'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' => 'o',
+            'ù' | 'ú' | 'û' | 'ü' => 'u',
+            'ý' | 'ÿ' => 'y',
+            c => c,
+        })
+        .collect()
+}
+
 // utility functions for getting text from the dom
 
-const INCLUDED_TAGS: &[&str] = &[
-    "b",
-    "strong",
-    "em",
-    "mark",
-    "cite",
-    "dfn",
-];
+pub(crate) const INCLUDED_TAGS: &[&str] = &["b", "strong", "em", "mark", "cite", "dfn"];
 
-fn el_to_string(node: NodeRef<Node>) -> String {
+pub(crate) fn el_to_string(node: NodeRef<Node>) -> String {
     el_to_string_with(node, &[], true, INCLUDED_TAGS)
 }
 
-fn el_to_string_with(node: NodeRef<Node>, pass_through: &[&str], pass_replace: bool, included: &[&str]) -> String {
+pub(crate) fn el_to_string_with(
+    node: NodeRef<Node>,
+    pass_through: &[&str],
+    pass_replace: bool,
+    included: &[&str],
+) -> String {
     let mut res = String::new();
     for item in node.children() {
         match item.value() {
@@ -432,22 +179,36 @@ fn el_to_string_with(node: NodeRef<Node>, pass_through: &[&str], pass_replace: b
                 res.push_str(text);
             }
             scraper::Node::Element(el) if included.contains(&el.name()) => {
-                res.push('<'); res.push_str(el.name()); res.push('>');
-                res.push_str(&el_to_string_with(item, pass_through, pass_replace, included));
-                res.push_str("</"); res.push_str(el.name()); res.push('>');
+                res.push('<');
+                res.push_str(el.name());
+                res.push('>');
+                res.push_str(&el_to_string_with(
+                    item,
+                    pass_through,
+                    pass_replace,
+                    included,
+                ));
+                res.push_str("</");
+                res.push_str(el.name());
+                res.push('>');
                 res.push(' ');
             }
             scraper::Node::Element(el) if pass_through.contains(&el.name()) => {
                 if pass_replace {
                     res.push_str("<i>");
                 }
-                res.push_str(&el_to_string_with(item, pass_through, pass_replace, included));
+                res.push_str(&el_to_string_with(
+                    item,
+                    pass_through,
+                    pass_replace,
+                    included,
+                ));
                 if pass_replace {
                     res.push_str("</i>");
                 }
                 res.push(' ');
             }
-            _ => {},
+            _ => {}
         }
     }
     res.trim()
@@ -458,4 +219,59 @@ fn el_to_string_with(node: NodeRef<Node>, pass_through: &[&str], pass_replace: b
         .replace(" ?", "?")
         .replace(" .", ".")
         .replace(" )", ")")
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_a_missing_word_is_a_miss() {
+        let mismatch = ScrapeError::WordMismatch {
+            expected: "dgo".to_string(),
+            found: "dog".to_string(),
+        };
+        assert!(mismatch.is_miss());
+        assert!(ScrapeError::Status(StatusCode::NOT_FOUND).is_miss());
+        assert!(ScrapeError::Restricted.is_miss());
+        assert!(ScrapeError::NotFound.is_miss());
+
+        let fetch = reqwest::Client::new().get("not a url").build().unwrap_err();
+        assert!(!ScrapeError::Fetch(fetch).is_miss());
+        assert!(!ScrapeError::Status(StatusCode::INTERNAL_SERVER_ERROR).is_miss());
+        assert!(!ScrapeError::Status(StatusCode::TOO_MANY_REQUESTS).is_miss());
+        assert!(!ScrapeError::MissingSelector(".definitions").is_miss());
+        assert!(!ScrapeError::NotRecorded("https://example.com".to_string()).is_miss());
+        assert!(!ScrapeError::CircuitOpen.is_miss());
+    }
+
+    #[test]
+    fn headwords_match_by_case_and_spelling() {
+        assert_eq!(
+            HeadwordMatch::between("dog", "dog"),
+            Some(HeadwordMatch::Exact)
+        );
+        assert_eq!(
+            HeadwordMatch::between("monday", "Monday"),
+            Some(HeadwordMatch::Case)
+        );
+        assert_eq!(
+            HeadwordMatch::between("Polish", "polish"),
+            Some(HeadwordMatch::Case)
+        );
+        assert_eq!(
+            HeadwordMatch::between("cafe", "café"),
+            Some(HeadwordMatch::Variant)
+        );
+        assert_eq!(
+            HeadwordMatch::between("email", "e-mail"),
+            Some(HeadwordMatch::Variant)
+        );
+        assert_eq!(
+            HeadwordMatch::between("oclock", "o'clock"),
+            Some(HeadwordMatch::Variant)
+        );
+        assert_eq!(HeadwordMatch::between("dogs", "dog"), None);
+        assert_eq!(HeadwordMatch::between("ran", "run"), None);
+    }
+}
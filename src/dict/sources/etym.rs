@@ -0,0 +1,166 @@
+use ego_tree::NodeRef;
+use regex::Regex;
+use scraper::{node::Text, ElementRef, Html, Node};
+
+use crate::dict::{
+    scrape::{
+        check_headword, el_to_string_with, find, find_loop, require, HeadwordMatch, ScrapeError,
+        INCLUDED_TAGS,
+    },
+    Origin,
+};
+
+use super::{with_path, DictionarySource, Expected, SourceResult};
+
+const URL_BASE: &str = "www.etymonline.com";
+
+/// Word origins from the Online Etymology Dictionary.
+pub(crate) struct Etymonline;
+
+impl DictionarySource for Etymonline {
+    fn id(&self) -> &'static str {
+        "etym"
+    }
+
+    fn name(&self) -> &'static str {
+        "Etymonline"
+    }
+
+    fn expected(&self) -> Expected {
+        Expected {
+            origins: 1,
+            ..Default::default()
+        }
+    }
+
+    fn host(&self) -> &'static str {
+        URL_BASE
+    }
+
+    fn url(&self, base: &str, word: &str) -> String {
+        with_path(base, &["word", word])
+    }
+
+    fn parse(&self, word: &str, body: &str) -> Result<SourceResult, ScrapeError> {
+        let doc = Html::parse_document(body);
+
+        fn find_startswith<'a>(node: NodeRef<'a, Node>, pat: &str) -> Option<NodeRef<'a, Node>> {
+            for child in node.children() {
+                if !child.value().is_element() {
+                    continue;
+                }
+                let el = child.value().as_element().unwrap();
+                match el.attr("class") {
+                    Some(class) if class.starts_with(pat) => {
+                        return Some(child);
+                    }
+                    _ => {
+                        let rec = find_startswith(child, pat);
+                        if rec.is_some() {
+                            return rec;
+                        }
+                    }
+                }
+            }
+            None
+        }
+
+        let numbers = Regex::new(r"[0-9]+").unwrap();
+
+        let mut origins = Vec::new();
+        let mut headword = None;
+
+        find_loop!(doc, ".word--C9UPa", word_entry, {
+            let word_name = find_startswith(*word_entry, "word__name")
+                .ok_or(ScrapeError::MissingSelector("[class^=word__name]"))?;
+
+            let word_name_text: Vec<&Text> = word_name
+                .children()
+                .filter_map(|child| match child.value() {
+                    Node::Text(text) => Some(text),
+                    _ => None,
+                })
+                .collect();
+
+            let (Some(real_word_name), Some(word_name_suffix)) =
+                (word_name_text.first(), word_name_text.last())
+            else {
+                return Err(ScrapeError::MissingSelector("[class^=word__name]"));
+            };
+            let real_word_name = real_word_name.to_string();
+
+            if origins.is_empty() {
+                // the page has nothing on the word itself
+                headword = check_headword(word, real_word_name)?;
+            } else if HeadwordMatch::between(word, &real_word_name).is_none() {
+                break;
+            }
+
+            let part_of_speech = match numbers
+                .replace_all(word_name_suffix, "")
+                .to_string()
+                .as_str()
+            {
+                "(n.)" => "noun",
+                "(v.)" => "verb",
+                "(adj.)" => "adjective",
+                "(adv.)" => "adverb",
+                "(interj.)" => "interjection",
+                "(prep.)" => "preposition",
+                "(pron.)" => "pronoun",
+                text if text.trim().is_empty() => "",
+                text => {
+                    warn!("`{}` doesn't know the part of speech `{}`", self.id(), text);
+                    text
+                }
+            }
+            .to_string();
+
+            let mut origin = String::new();
+
+            let entry = word_name
+                .parent()
+                .and_then(ElementRef::wrap)
+                .ok_or(ScrapeError::MissingSelector("[class^=word__name]"))?;
+
+            find_loop!(require!(entry, "section")?, "p", p, {
+                if !origin.is_empty() {
+                    origin.push_str("<br>");
+                }
+                origin.push_str(&el_to_string_with(*p, &["span", "a"], true, INCLUDED_TAGS));
+            });
+
+            origins.push(Origin {
+                part_of_speech,
+                origin,
+            });
+        });
+
+        Ok(SourceResult {
+            headword,
+            origins,
+            attribution: URL_BASE.to_string(),
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dict::sources::fixtures;
+
+    #[test]
+    fn parses_saved_page() {
+        fixtures::assert_golden(&Etymonline, "dog");
+    }
+
+    #[test]
+    fn other_words_are_a_mismatch() {
+        let page = fixtures::page(&Etymonline, "dog");
+        assert!(matches!(
+            Etymonline.parse("dogwood", &page),
+            Err(ScrapeError::WordMismatch { found, .. }) if found == "dog"
+        ));
+    }
+}
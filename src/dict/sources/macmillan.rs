@@ -0,0 +1,172 @@
+use scraper::{ElementRef, Html};
+
+use crate::dict::{
+    scrape::{
+        check_headword, el_to_string, el_to_string_with, find, find_loop, require, ScrapeError,
+        INCLUDED_TAGS,
+    },
+    Definition,
+};
+
+use super::{with_path, DictionarySource, SourceResult};
+
+const URL_BASE: &str = "www.macmillandictionary.com";
+
+/// Definitions from the American edition of the Macmillan Dictionary, grouped into senses.
+pub(crate) struct Macmillan;
+
+impl DictionarySource for Macmillan {
+    fn id(&self) -> &'static str {
+        "macmillan"
+    }
+
+    fn name(&self) -> &'static str {
+        "Macmillan"
+    }
+
+    fn host(&self) -> &'static str {
+        URL_BASE
+    }
+
+    fn url(&self, base: &str, word: &str) -> String {
+        // "kick the bucket" and "o'clock" are kept at kick-the-bucket and o-clock
+        let slug = word.replace([' ', '\'', '’'], "-");
+        with_path(base, &["us", "dictionary", "american", &slug])
+    }
+
+    fn parse(&self, word: &str, body: &str) -> Result<SourceResult, ScrapeError> {
+        let doc = Html::parse_document(body);
+
+        // a word it doesn't have is sent on to the search page, which says
+        // so above its "did you mean"s
+        if find!(doc, "h1").is_some_and(|h1| {
+            h1.text()
+                .collect::<String>()
+                .trim_start()
+                .starts_with("Sorry, no search result")
+        }) {
+            return Err(ScrapeError::NotFound);
+        }
+
+        let word_area = require!(doc, ".left-content")?;
+
+        let real_word = el_to_string(
+            word_area
+                .first_child()
+                .and_then(|child| child.first_child())
+                .and_then(|child| child.first_child())
+                .and_then(|child| child.first_child())
+                .ok_or(ScrapeError::MissingSelector(
+                    ".left-content > * > * > * > *",
+                ))?,
+        );
+        let headword = check_headword(word, real_word)?;
+
+        let part_of_speech = el_to_string(*require!(word_area, ".PART-OF-SPEECH")?);
+
+        let definition_area = word_area
+            .children()
+            .find(|child| {
+                child.value().is_element() && child.value().as_element().unwrap().attrs.is_empty()
+            })
+            .and_then(|child| {
+                child.children().find(|child| {
+                    child.value().is_element()
+                        && child.value().as_element().unwrap().attrs.is_empty()
+                })
+            })
+            .ok_or(ScrapeError::MissingSelector(
+                ".left-content > :not([class]) > :not([class])",
+            ))?;
+
+        let ol = require!(ElementRef::wrap(definition_area).unwrap(), "ol")?;
+
+        let mut definitions = Vec::new();
+
+        find_loop!(ol, "li", item, {
+            let sense_body = if let Some(el) = find!(item, ".SENSE-BODY") {
+                el
+            } else {
+                continue;
+            };
+
+            let mut sense = Vec::new();
+
+            find_loop!(sense_body, ".dflex", body, {
+                let meaning = if let Some(el) = find!(body, ".DEFINITION") {
+                    el
+                } else {
+                    continue;
+                };
+
+                let meaning = el_to_string_with(*meaning, &["a", "span"], false, INCLUDED_TAGS);
+
+                let mut examples = Vec::new();
+                if let Some(examples_el) = find!(body, ".EXAMPLES") {
+                    for example in examples_el.children() {
+                        if !example.value().is_element()
+                            || example.value().as_element().unwrap().name() != "p"
+                        {
+                            continue;
+                        }
+                        examples.push(
+                            el_to_string_with(example, &["a", "span"], false, INCLUDED_TAGS)
+                                .replace("\n", ""),
+                        );
+                    }
+                }
+
+                sense.push(Definition {
+                    part_of_speech: part_of_speech.clone(),
+                    meaning,
+                    examples,
+                });
+            });
+
+            if !sense.is_empty() {
+                definitions.push(sense);
+            }
+        });
+
+        Ok(SourceResult {
+            headword,
+            definitions,
+            attribution: URL_BASE.to_string(),
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dict::sources::fixtures;
+
+    #[test]
+    fn parses_saved_page() {
+        fixtures::assert_golden(&Macmillan, "run");
+    }
+
+    #[test]
+    fn other_words_are_a_mismatch() {
+        let page = fixtures::page(&Macmillan, "run");
+        assert!(matches!(
+            Macmillan.parse("ran", &page),
+            Err(ScrapeError::WordMismatch { found, .. }) if found == "run"
+        ));
+    }
+
+    #[test]
+    fn the_search_page_is_a_miss() {
+        let page = fixtures::page(&Macmillan, "dgo");
+        assert!(matches!(
+            Macmillan.parse("dgo", &page),
+            Err(ScrapeError::NotFound)
+        ));
+        // a page without its entry that isn't the search page is still broken
+        assert!(matches!(
+            Macmillan.parse("dgo", "<html><body><h1>Maintenance</h1></body></html>"),
+            Err(ScrapeError::MissingSelector(".left-content"))
+        ));
+    }
+}
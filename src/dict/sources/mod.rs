@@ -0,0 +1,631 @@
+mod etym;
+mod macmillan;
+mod stock;
+mod vocab;
+mod wiki;
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use reqwest::Url;
+use rocket::{fairing::AdHoc, Build, Rocket};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    breaker::{BreakerConfig, CircuitBreaker},
+    http::Http,
+    politeness::Politeness,
+    scrape::{ScrapeError, PROTOCOL},
+    Definition, Origin,
+};
+
+pub(crate) use etym::Etymonline;
+pub(crate) use macmillan::Macmillan;
+pub(crate) use stock::AdobeStock;
+pub(crate) use vocab::Vocabulary;
+pub(crate) use wiki::Wiktionary;
+
+/// Everything a single source knows about a word.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub(crate) struct SourceResult {
+    /// The word the page is for, when that's spelled differently from the
+    /// word asked for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) headword: Option<String>,
+    pub(crate) overview: Vec<String>,
+    /// Each sense is its main definition followed by any subsenses.
+    pub(crate) definitions: Vec<Vec<Definition>>,
+    pub(crate) origins: Vec<Origin>,
+    /// Links to pictures of the word.
+    pub(crate) media: Vec<String>,
+    /// Who to credit for all of the above.
+    pub(crate) attribution: String,
+}
+
+impl SourceResult {
+    /// Whether the source found nothing worth crediting it for.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.overview.is_empty()
+            && self.definitions.is_empty()
+            && self.origins.is_empty()
+            && self.media.is_empty()
+    }
+}
+
+/// The least a source should find for a common word, which is how the
+/// canary tells a working parser from one that quietly finds nothing.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct Expected {
+    pub(crate) definitions: usize,
+    pub(crate) origins: usize,
+    pub(crate) media: usize,
+}
+
+impl Expected {
+    /// What `result` is missing, if anything.
+    pub(crate) fn shortfall(&self, result: &SourceResult) -> Option<String> {
+        [
+            ("definitions", result.definitions.len(), self.definitions),
+            ("origins", result.origins.len(), self.origins),
+            ("media", result.media.len(), self.media),
+        ]
+        .into_iter()
+        .find(|(_, found, expected)| found < expected)
+        .map(|(what, found, expected)| {
+            format!("found {} {}, expected at least {}", found, what, expected)
+        })
+    }
+}
+
+#[rocket::async_trait]
+pub(crate) trait DictionarySource: Send + Sync {
+    /// A short name that doesn't change, for the api and config.
+    fn id(&self) -> &'static str;
+
+    /// What to call the source on the page.
+    fn name(&self) -> &'static str;
+
+    /// Whether the source should be asked about `word` at all.
+    fn allows(&self, _word: &str) -> bool {
+        true
+    }
+
+    /// What a healthy answer for a common word has in it.
+    fn expected(&self) -> Expected {
+        Expected {
+            definitions: 1,
+            ..Default::default()
+        }
+    }
+
+    /// The site the source lives on, which is also who gets the credit.
+    fn host(&self) -> &'static str;
+
+    /// The page the source keeps `word` on, under `base` (the scheme and
+    /// host, usually `https://` and `host()`). `word` can be a phrase, or
+    /// have apostrophes or accents in it, so it has to be encoded the way
+    /// the site expects, usually with `with_path` or `with_query`.
+    fn url(&self, base: &str, word: &str) -> String;
+
+    /// Pulls what the source knows about `word` out of its page, without
+    /// touching the network, so it can be tested against saved pages.
+    fn parse(&self, word: &str, body: &str) -> Result<SourceResult, ScrapeError>;
+
+    async fn scrape(
+        &self,
+        http: &Http,
+        upstream: &Upstream,
+        word: &str,
+    ) -> Result<SourceResult, ScrapeError> {
+        if !self.allows(word) {
+            return Err(ScrapeError::Restricted);
+        }
+
+        let url = self.url(&upstream.base_url, word);
+        let page = http.fetch(&url, &upstream.politeness).await?;
+        match self.parse(word, &page.body) {
+            // the site sent us to the page it keeps the word under, so
+            // whatever word that is counts
+            Err(ScrapeError::WordMismatch { found, .. }) if page.redirected_to.is_some() => {
+                let mut result = self.parse(&found, &page.body)?;
+                result.headword = Some(found);
+                Ok(result)
+            }
+            result => result,
+        }
+    }
+}
+
+/// `base` with `segments` added to its path, each percent-encoded so that
+/// nothing in a word can reach outside its own segment.
+pub(crate) fn with_path(base: &str, segments: &[&str]) -> String {
+    with_query(base, segments, &[])
+}
+
+/// `base` with `segments` added to its path and `pairs` as its query string,
+/// encoded the way a search form would.
+pub(crate) fn with_query(base: &str, segments: &[&str], pairs: &[(&str, &str)]) -> String {
+    let mut url = Url::parse(base).expect("base urls are checked when the sources are set up");
+    url.path_segments_mut()
+        .expect("base urls are checked when the sources are set up")
+        .pop_if_empty()
+        .extend(segments);
+    if !pairs.is_empty() {
+        url.query_pairs_mut().extend_pairs(pairs);
+    }
+    url.into()
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub(crate) struct SourceConfig {
+    /// Whether to ask the source at all.
+    pub(crate) enabled: bool,
+    /// Where to fetch pages from instead of the source's own site, scheme
+    /// included, e.g. a local stand-in server or a mirror.
+    pub(crate) base_url: Option<String>,
+    #[serde(flatten)]
+    pub(crate) politeness: Politeness,
+}
+
+impl Default for SourceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            base_url: None,
+            politeness: Politeness::default(),
+        }
+    }
+}
+
+/// Where a source's pages come from, and how gently to fetch them.
+pub(crate) struct Upstream {
+    pub(crate) base_url: String,
+    pub(crate) politeness: Politeness,
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub(crate) struct ScrapeConfig {
+    /// How long a source gets to answer.
+    pub(crate) timeout_ms: u64,
+    /// Timeouts for particular sources, by id.
+    pub(crate) timeouts: HashMap<String, u64>,
+    /// How long a whole lookup can take, however long the sources get.
+    pub(crate) deadline_ms: u64,
+    /// How long a lookup waits before answering with the sources that are
+    /// done, leaving the rest to finish into the cache.
+    pub(crate) respond_ms: u64,
+    /// Settings for particular sources, by id.
+    pub(crate) sources: HashMap<String, SourceConfig>,
+    /// When to stop asking a source that keeps failing.
+    pub(crate) breaker: BreakerConfig,
+}
+
+impl Default for ScrapeConfig {
+    fn default() -> Self {
+        Self {
+            timeout_ms: 8_000,
+            timeouts: HashMap::new(),
+            deadline_ms: 15_000,
+            respond_ms: 4_000,
+            sources: HashMap::new(),
+            breaker: BreakerConfig::default(),
+        }
+    }
+}
+
+/// Every source `Word::scrape_each` asks, in order of preference: definitions
+/// are shown in this order, and the first origins found are the ones used.
+pub(crate) struct SourceRegistry {
+    sources: Vec<Arc<dyn DictionarySource>>,
+    /// What every source fetches its pages with.
+    pub(crate) http: Arc<Http>,
+    /// Where each source's pages are fetched from, by id.
+    upstreams: HashMap<&'static str, Upstream>,
+    breakers: HashMap<&'static str, CircuitBreaker>,
+    timeout: Duration,
+    timeouts: HashMap<String, Duration>,
+    pub(crate) deadline: Duration,
+    pub(crate) respond_within: Duration,
+}
+
+impl SourceRegistry {
+    pub(crate) fn new(
+        sources: Vec<Arc<dyn DictionarySource>>,
+        http: Arc<Http>,
+        config: &ScrapeConfig,
+    ) -> Self {
+        let source_config = |source: &Arc<dyn DictionarySource>| config.sources.get(source.id());
+
+        let sources: Vec<_> = sources
+            .into_iter()
+            .filter(|source| source_config(source).is_none_or(|config| config.enabled))
+            .collect();
+
+        let upstreams = sources
+            .iter()
+            .map(|source| {
+                let config = source_config(source);
+                let base_url = config
+                    .and_then(|config| config.base_url.as_deref())
+                    .map(|base| base.trim_end_matches('/').to_string())
+                    .unwrap_or_else(|| format!("{}://{}", PROTOCOL, source.host()));
+                let politeness = config
+                    .map(|config| config.politeness.clone())
+                    .unwrap_or_default();
+                (
+                    source.id(),
+                    Upstream {
+                        base_url,
+                        politeness,
+                    },
+                )
+            })
+            .collect();
+
+        let breakers = sources
+            .iter()
+            .map(|source| (source.id(), CircuitBreaker::new(&config.breaker)))
+            .collect();
+
+        Self {
+            sources,
+            http,
+            upstreams,
+            breakers,
+            timeout: Duration::from_millis(config.timeout_ms),
+            timeouts: config
+                .timeouts
+                .iter()
+                .map(|(id, ms)| (id.clone(), Duration::from_millis(*ms)))
+                .collect(),
+            deadline: Duration::from_millis(config.deadline_ms),
+            respond_within: Duration::from_millis(config.respond_ms),
+        }
+    }
+
+    /// Every source there is.
+    pub(crate) fn all(http: Arc<Http>, config: &ScrapeConfig) -> Self {
+        Self::new(
+            vec![
+                Arc::new(Etymonline),
+                Arc::new(Macmillan),
+                Arc::new(Vocabulary),
+                Arc::new(Wiktionary),
+                Arc::new(AdobeStock),
+            ],
+            http,
+            config,
+        )
+    }
+
+    pub(crate) fn fairing() -> AdHoc {
+        AdHoc::try_on_ignite("Sources", |rocket: Rocket<Build>| async move {
+            let config: ScrapeConfig = match rocket.figment().extract_inner("scrape") {
+                Ok(config) => config,
+                Err(err) if err.missing() => ScrapeConfig::default(),
+                Err(err) => {
+                    error!("invalid scrape config: {}", err);
+                    return Err(rocket);
+                }
+            };
+
+            for (id, source) in &config.sources {
+                let Some(base_url) = &source.base_url else {
+                    continue;
+                };
+                if Url::parse(base_url).map_or(true, |url| url.cannot_be_a_base()) {
+                    error!("`{}` has an invalid base_url: {}", id, base_url);
+                    return Err(rocket);
+                }
+            }
+
+            let Some(http) = rocket.state::<Arc<Http>>().cloned() else {
+                error!("the sources need http to be attached first");
+                return Err(rocket);
+            };
+
+            Ok(rocket.manage(Arc::new(SourceRegistry::all(http, &config))))
+        })
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Arc<dyn DictionarySource>> {
+        self.sources.iter()
+    }
+
+    /// Where `source` fetches its pages from, and how.
+    pub(crate) fn upstream(&self, source: &dyn DictionarySource) -> &Upstream {
+        &self.upstreams[source.id()]
+    }
+
+    pub(crate) fn breaker(&self, source: &dyn DictionarySource) -> &CircuitBreaker {
+        &self.breakers[source.id()]
+    }
+
+    /// How long `source` gets to answer.
+    pub(crate) fn timeout(&self, source: &dyn DictionarySource) -> Duration {
+        self.timeouts
+            .get(source.id())
+            .copied()
+            .unwrap_or(self.timeout)
+    }
+}
+
+impl Default for SourceRegistry {
+    fn default() -> Self {
+        Self::all(Arc::default(), &ScrapeConfig::default())
+    }
+}
+
+/// Helpers for testing parsers against the pages saved in `tests/fixtures`,
+/// and a stand-in source for testing everything built on top of them.
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use std::{
+        fs,
+        path::PathBuf,
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use super::{DictionarySource, Expected, Http, ScrapeError, SourceResult, Upstream};
+    use crate::dict::Definition;
+
+    type Answer = dyn Fn(&str) -> Result<SourceResult, ScrapeError> + Send + Sync;
+
+    /// A source that doesn't touch the network, answering every word with
+    /// whatever its closure makes of it and counting how often it's asked.
+    pub(crate) struct Stub {
+        id: &'static str,
+        delay: Duration,
+        expected: Expected,
+        answer: Box<Answer>,
+        scrapes: AtomicUsize,
+    }
+
+    impl Stub {
+        pub(crate) fn new(
+            id: &'static str,
+            answer: impl Fn(&str) -> Result<SourceResult, ScrapeError> + Send + Sync + 'static,
+        ) -> Self {
+            Self {
+                id,
+                delay: Duration::ZERO,
+                expected: Expected {
+                    definitions: 1,
+                    ..Default::default()
+                },
+                answer: Box::new(answer),
+                scrapes: AtomicUsize::new(0),
+            }
+        }
+
+        /// Takes `delay_ms` to answer.
+        pub(crate) fn delayed(mut self, delay_ms: u64) -> Self {
+            self.delay = Duration::from_millis(delay_ms);
+            self
+        }
+
+        /// Expects `expected` from a healthy answer, for the canary.
+        pub(crate) fn expecting(mut self, expected: Expected) -> Self {
+            self.expected = expected;
+            self
+        }
+
+        /// How many times it has been asked about a word.
+        pub(crate) fn scrapes(&self) -> usize {
+            self.scrapes.load(Ordering::SeqCst)
+        }
+    }
+
+    #[rocket::async_trait]
+    impl DictionarySource for Stub {
+        fn id(&self) -> &'static str {
+            self.id
+        }
+
+        fn name(&self) -> &'static str {
+            self.id
+        }
+
+        fn expected(&self) -> Expected {
+            self.expected
+        }
+
+        fn host(&self) -> &'static str {
+            self.id
+        }
+
+        fn url(&self, base: &str, word: &str) -> String {
+            format!("{}/{}", base, word)
+        }
+
+        fn parse(&self, word: &str, _body: &str) -> Result<SourceResult, ScrapeError> {
+            (self.answer)(word)
+        }
+
+        async fn scrape(
+            &self,
+            _http: &Http,
+            _upstream: &Upstream,
+            word: &str,
+        ) -> Result<SourceResult, ScrapeError> {
+            self.scrapes.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(self.delay).await;
+            self.parse(word, "")
+        }
+    }
+
+    /// A result with a single sense, meaning `meaning`.
+    pub(crate) fn defined_as(meaning: impl Into<String>) -> SourceResult {
+        SourceResult {
+            definitions: vec![vec![Definition {
+                part_of_speech: "noun".to_string(),
+                meaning: meaning.into(),
+                examples: Vec::new(),
+            }]],
+            ..Default::default()
+        }
+    }
+
+    fn dir(source: &dyn DictionarySource) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(source.id())
+    }
+
+    /// The saved page `source` serves for `word`.
+    pub(crate) fn page(source: &dyn DictionarySource, word: &str) -> String {
+        let path = dir(source).join(format!("{}.html", word));
+        fs::read_to_string(&path).unwrap_or_else(|err| panic!("{}: {}", path.display(), err))
+    }
+
+    /// Parses the saved page for `word` and checks the result against the
+    /// `.json` next to it. Set `UPDATE_GOLDEN` to rewrite that file instead,
+    /// after a deliberate change to the parser or a refreshed page.
+    pub(crate) fn assert_golden(source: &dyn DictionarySource, word: &str) {
+        let result = source
+            .parse(word, &page(source, word))
+            .unwrap_or_else(|err| panic!("{} couldn't parse `{}`: {}", source.id(), word, err));
+        let actual = serde_json::to_value(&result).unwrap();
+
+        let path = dir(source).join(format!("{}.json", word));
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            let json = serde_json::to_string_pretty(&actual).unwrap();
+            fs::write(&path, json + "\n").unwrap();
+            return;
+        }
+
+        let golden = fs::read_to_string(&path)
+            .unwrap_or_else(|err| panic!("{}: {} (run with UPDATE_GOLDEN=1)", path.display(), err));
+        let expected: serde_json::Value = serde_json::from_str(&golden).unwrap();
+        assert_eq!(
+            actual,
+            expected,
+            "{} no longer matches {}",
+            source.id(),
+            path.display()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dict::http::{HttpConfig, HttpMode, Recording};
+
+    #[test]
+    fn sources_can_be_moved_or_turned_off() {
+        let config = ScrapeConfig {
+            sources: HashMap::from([
+                (
+                    "vocab".to_string(),
+                    SourceConfig {
+                        base_url: Some("http://localhost:9000/".to_string()),
+                        ..Default::default()
+                    },
+                ),
+                (
+                    "stock".to_string(),
+                    SourceConfig {
+                        enabled: false,
+                        ..Default::default()
+                    },
+                ),
+            ]),
+            ..Default::default()
+        };
+        let sources = SourceRegistry::all(Arc::default(), &config);
+
+        let ids: Vec<_> = sources.iter().map(|source| source.id()).collect();
+        assert_eq!(ids, ["etym", "macmillan", "vocab", "wiki"]);
+
+        let vocab = sources.upstream(&Vocabulary);
+        assert_eq!(vocab.base_url, "http://localhost:9000");
+        assert_eq!(
+            Vocabulary.url(&vocab.base_url, "dog"),
+            "http://localhost:9000/dictionary/definition.ajax?search=dog&lang=en",
+        );
+        assert_eq!(
+            sources.upstream(&Wiktionary).base_url,
+            "https://en.wiktionary.org"
+        );
+    }
+
+    #[rocket::async_test]
+    async fn redirects_are_followed_to_another_word() {
+        let dir = std::env::temp_dir().join(format!("dict-redirects-{}", std::process::id()));
+        let http = Http::new(&HttpConfig {
+            mode: HttpMode::Replay,
+            dir: dir.clone(),
+            ..Default::default()
+        })
+        .unwrap();
+        let upstream = Upstream {
+            base_url: "https://www.vocabulary.com".to_string(),
+            politeness: Politeness::default(),
+        };
+
+        // the site sends "hound" on to its page for "dog"
+        let url = Vocabulary.url(&upstream.base_url, "hound");
+        http.save(&Recording {
+            url: url.clone(),
+            redirected_to: Some(Vocabulary.url(&upstream.base_url, "dog")),
+            status: 200,
+            headers: Vec::new(),
+            body: fixtures::page(&Vocabulary, "dog"),
+        })
+        .await
+        .unwrap();
+
+        let result = Vocabulary.scrape(&http, &upstream, "hound").await.unwrap();
+        assert_eq!(result.headword.as_deref(), Some("dog"));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn words_are_encoded_the_way_each_source_wants() {
+        let urls = |word| {
+            SourceRegistry::default()
+                .iter()
+                .map(|source| (source.id(), source.url("https://example.com", word)))
+                .collect::<HashMap<_, _>>()
+        };
+
+        let phrase = urls("kick the bucket");
+        assert_eq!(
+            phrase["etym"],
+            "https://example.com/word/kick%20the%20bucket"
+        );
+        assert_eq!(
+            phrase["macmillan"],
+            "https://example.com/us/dictionary/american/kick-the-bucket"
+        );
+        assert_eq!(
+            phrase["vocab"],
+            "https://example.com/dictionary/definition.ajax?search=kick+the+bucket&lang=en"
+        );
+        assert_eq!(phrase["wiki"], "https://example.com/wiki/kick_the_bucket");
+        assert_eq!(
+            phrase["stock"],
+            "https://example.com/search?k=kick+the+bucket"
+        );
+
+        assert_eq!(
+            urls("o'clock")["macmillan"],
+            "https://example.com/us/dictionary/american/o-clock"
+        );
+        assert_eq!(urls("café")["wiki"], "https://example.com/wiki/caf%C3%A9");
+        assert_eq!(
+            urls("naïve")["vocab"],
+            "https://example.com/dictionary/definition.ajax?search=na%C3%AFve&lang=en"
+        );
+        // nothing in a word gets out of its place in the url
+        assert_eq!(urls("a/b?c")["wiki"], "https://example.com/wiki/a%2Fb%3Fc");
+        assert_eq!(
+            urls("a&lang=fr")["vocab"],
+            "https://example.com/dictionary/definition.ajax?search=a%26lang%3Dfr&lang=en"
+        );
+    }
+}
@@ -0,0 +1,97 @@
+use scraper::Html;
+
+use crate::dict::{
+    scrape::{find, find_loop, require, ScrapeError},
+    RESTRICTOR,
+};
+
+use super::{with_query, DictionarySource, Expected, SourceResult};
+
+const URL_BASE: &str = "stock.adobe.com";
+
+/// Pictures of the word from Adobe Stock, minus anything the restrictor catches.
+pub(crate) struct AdobeStock;
+
+impl DictionarySource for AdobeStock {
+    fn id(&self) -> &'static str {
+        "stock"
+    }
+
+    fn name(&self) -> &'static str {
+        "Adobe Stock"
+    }
+
+    fn allows(&self, word: &str) -> bool {
+        !RESTRICTOR.is_restricted(word.to_lowercase().as_str())
+    }
+
+    fn expected(&self) -> Expected {
+        Expected {
+            media: 1,
+            ..Default::default()
+        }
+    }
+
+    fn host(&self) -> &'static str {
+        URL_BASE
+    }
+
+    fn url(&self, base: &str, word: &str) -> String {
+        with_query(base, &["search"], &[("k", word)])
+    }
+
+    fn parse(&self, _word: &str, body: &str) -> Result<SourceResult, ScrapeError> {
+        let doc = Html::parse_document(body);
+
+        let mut imgs = Vec::new();
+
+        find_loop!(doc, ".search-result-cell", img_div, {
+            let img_el = require!(img_div, "img")?.value();
+
+            let alt = img_el
+                .attr("alt")
+                .ok_or(ScrapeError::MissingSelector("img[alt]"))?;
+            if RESTRICTOR.is_restricted(alt.to_lowercase().as_str()) {
+                continue;
+            }
+
+            imgs.push(
+                img_el
+                    .attr("src")
+                    .ok_or(ScrapeError::MissingSelector("img[src]"))?
+                    .to_string(),
+            );
+
+            if imgs.len() == 6 {
+                break;
+            }
+        });
+
+        Ok(SourceResult {
+            media: imgs,
+            attribution: URL_BASE.to_string(),
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dict::sources::fixtures;
+
+    #[test]
+    fn parses_saved_page() {
+        fixtures::assert_golden(&AdobeStock, "dog");
+    }
+
+    #[test]
+    fn images_without_alt_text_name_the_attribute() {
+        let page =
+            r#"<div class="search-result-cell"><img src="https://t4.ftcdn.net/jpg/dog.jpg"></div>"#;
+        assert!(matches!(
+            AdobeStock.parse("dog", page),
+            Err(ScrapeError::MissingSelector("img[alt]"))
+        ));
+    }
+}
@@ -0,0 +1,144 @@
+use scraper::Html;
+
+use crate::dict::{
+    scrape::{
+        check_headword, el_to_string, el_to_string_with, find, find_loop, require, ScrapeError,
+        INCLUDED_TAGS,
+    },
+    Definition,
+};
+
+use super::{with_query, DictionarySource, SourceResult};
+
+const URL_BASE: &str = "www.vocabulary.com";
+
+/// Definitions and a short overview from Vocabulary.com.
+pub(crate) struct Vocabulary;
+
+impl DictionarySource for Vocabulary {
+    fn id(&self) -> &'static str {
+        "vocab"
+    }
+
+    fn name(&self) -> &'static str {
+        "Vocabulary.com"
+    }
+
+    fn host(&self) -> &'static str {
+        URL_BASE
+    }
+
+    fn url(&self, base: &str, word: &str) -> String {
+        with_query(
+            base,
+            &["dictionary", "definition.ajax"],
+            &[("search", word), ("lang", "en")],
+        )
+    }
+
+    fn parse(&self, word: &str, body: &str) -> Result<SourceResult, ScrapeError> {
+        let doc = Html::parse_document(body);
+
+        // a word it doesn't have gets a page saying so, and maybe some
+        // suggestions, instead of a 404
+        if find!(doc.root_element(), ".noresults").is_some() {
+            return Err(ScrapeError::NotFound);
+        }
+
+        let word_area = require!(doc.root_element(), ".word-area")?;
+
+        let headword = check_headword(word, el_to_string(*require!(word_area, "h1")?))?;
+
+        let short_overview = Some(el_to_string_with(
+            *require!(word_area, ".short")?,
+            &[],
+            true,
+            &[INCLUDED_TAGS, &["i"]].concat(),
+        ))
+        .filter(|s| !s.is_empty());
+        let long_overview = Some(el_to_string_with(
+            *require!(word_area, ".long")?,
+            &[],
+            true,
+            &[INCLUDED_TAGS, &["i"]].concat(),
+        ))
+        .filter(|s| !s.is_empty());
+
+        let ol = require!(require!(doc, ".word-definitions")?, "ol")?;
+
+        let mut definitions = Vec::new();
+
+        find_loop!(ol, "li", item, {
+            let def_area = require!(item, ".definition")?;
+
+            let part_of_speech = el_to_string(*require!(def_area, ".pos-icon")?);
+
+            let meaning = el_to_string(*def_area);
+
+            let mut examples = Vec::new();
+
+            find_loop!(item, ".example", example, {
+                examples.push(el_to_string(*example).replace("\n", ""));
+            });
+
+            definitions.push(Definition {
+                part_of_speech,
+                meaning,
+                examples,
+            });
+        });
+
+        Ok(SourceResult {
+            headword,
+            overview: short_overview.into_iter().chain(long_overview).collect(),
+            definitions: definitions.into_iter().map(|def| vec![def]).collect(),
+            attribution: URL_BASE.to_string(),
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dict::sources::fixtures;
+
+    #[test]
+    fn parses_saved_page() {
+        fixtures::assert_golden(&Vocabulary, "dog");
+    }
+
+    #[test]
+    fn case_variants_are_the_same_word() {
+        let page = fixtures::page(&Vocabulary, "dog");
+        let result = Vocabulary.parse("Dog", &page).unwrap();
+        assert_eq!(result.headword.as_deref(), Some("dog"));
+        assert!(!result.definitions.is_empty());
+    }
+
+    #[test]
+    fn other_words_are_a_mismatch() {
+        let page = fixtures::page(&Vocabulary, "dog");
+        assert!(matches!(
+            Vocabulary.parse("dogs", &page),
+            Err(ScrapeError::WordMismatch { found, .. }) if found == "dog"
+        ));
+    }
+
+    #[test]
+    fn the_no_results_page_is_a_miss() {
+        let page = fixtures::page(&Vocabulary, "dgo");
+        assert!(matches!(
+            Vocabulary.parse("dgo", &page),
+            Err(ScrapeError::NotFound)
+        ));
+    }
+
+    #[test]
+    fn missing_word_area_names_the_selector() {
+        assert!(matches!(
+            Vocabulary.parse("dog", "<html><body><p>Sorry!</p></body></html>"),
+            Err(ScrapeError::MissingSelector(".word-area"))
+        ));
+    }
+}
@@ -0,0 +1,193 @@
+use scraper::{ElementRef, Html};
+
+use crate::dict::{
+    scrape::{el_to_string, el_to_string_with, find, require, ScrapeError, INCLUDED_TAGS},
+    Definition, Origin,
+};
+
+use super::{with_path, DictionarySource, Expected, SourceResult};
+
+const URL_BASE: &str = "en.wiktionary.org";
+
+/// Definitions and etymologies from the English section of Wiktionary.
+pub(crate) struct Wiktionary;
+
+impl DictionarySource for Wiktionary {
+    fn id(&self) -> &'static str {
+        "wiki"
+    }
+
+    fn name(&self) -> &'static str {
+        "Wiktionary"
+    }
+
+    fn expected(&self) -> Expected {
+        Expected {
+            definitions: 1,
+            origins: 1,
+            ..Default::default()
+        }
+    }
+
+    fn host(&self) -> &'static str {
+        URL_BASE
+    }
+
+    fn url(&self, base: &str, word: &str) -> String {
+        // page titles have underscores for spaces
+        with_path(base, &["wiki", &word.replace(' ', "_")])
+    }
+
+    fn parse(&self, _word: &str, body: &str) -> Result<SourceResult, ScrapeError> {
+        let doc = Html::parse_document(body);
+
+        let eng = require!(doc, "#English")?;
+
+        let mut origins = Vec::new();
+        let mut working_origin_p = String::new();
+
+        let mut definitions = Vec::new();
+
+        let mut last_origin_title = String::new();
+        let mut first_def_title = String::new();
+        let mut last_title = String::new();
+
+        let mut got_to_eng = false;
+        for child in eng.parent().unwrap().parent().unwrap().children() {
+            if !child.value().is_element() {
+                continue;
+            }
+            let el = child.value().as_element().unwrap();
+            let el_ref = ElementRef::wrap(child).unwrap();
+
+            if !got_to_eng {
+                got_to_eng = find!(el_ref, "#English").is_some();
+                continue;
+            }
+
+            match el.name() {
+                "h3" | "h4" | "h5" => {
+                    let title = require!(el_ref, ".mw-headline")?;
+                    last_title = el_to_string(*title).to_lowercase();
+                }
+                "ol" => {
+                    for grandchild in child.children() {
+                        if !grandchild.value().is_element() {
+                            continue;
+                        }
+
+                        let meaning = el_to_string_with(
+                            grandchild,
+                            &["a"],
+                            false,
+                            &[INCLUDED_TAGS, &["span", "i"]].concat(),
+                        );
+                        if meaning.is_empty() {
+                            continue;
+                        }
+
+                        let mut examples = Vec::new();
+
+                        if let Some(examples_list) =
+                            find!(ElementRef::wrap(grandchild).unwrap(), "dl")
+                        {
+                            for el in examples_list.children() {
+                                if !el.value().is_element()
+                                    || el.value().as_element().unwrap().name() != "dd"
+                                {
+                                    continue;
+                                }
+                                examples.push(el_to_string_with(
+                                    el,
+                                    &["span", "i"],
+                                    true,
+                                    INCLUDED_TAGS,
+                                ))
+                            }
+                        }
+
+                        definitions.push(Definition {
+                            part_of_speech: match last_title.as_str() {
+                                "numeral" | "number" | "letter" => "noun",
+                                text => text,
+                            }
+                            .to_owned(),
+                            meaning,
+                            examples,
+                        });
+
+                        if first_def_title.is_empty() {
+                            first_def_title = last_title.clone();
+                        }
+                    }
+                }
+                "hr" => break,
+                "p" if last_title.starts_with("etymology") => {
+                    if !working_origin_p.is_empty()
+                        && (origins.is_empty() || last_title != last_origin_title)
+                        && !first_def_title.is_empty()
+                    {
+                        origins.push(Origin {
+                            part_of_speech: first_def_title.clone(),
+                            origin: working_origin_p.clone(),
+                        });
+                        working_origin_p.clear();
+                        last_origin_title = last_title.clone();
+                    }
+
+                    // another paragraph to the working origin
+
+                    if !working_origin_p.is_empty() {
+                        working_origin_p.push_str("<br>");
+                    }
+
+                    working_origin_p.push_str(&el_to_string_with(
+                        child,
+                        &["span"],
+                        false,
+                        INCLUDED_TAGS,
+                    ));
+
+                    first_def_title.clear();
+                }
+                _ => {}
+            }
+        }
+
+        if !working_origin_p.is_empty() {
+            origins.push(Origin {
+                part_of_speech: first_def_title.clone(),
+                origin: working_origin_p,
+            });
+        }
+
+        Ok(SourceResult {
+            definitions: definitions.into_iter().map(|def| vec![def]).collect(),
+            origins,
+            attribution: URL_BASE.to_string(),
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dict::sources::fixtures;
+
+    #[test]
+    fn parses_saved_page() {
+        fixtures::assert_golden(&Wiktionary, "dog");
+    }
+
+    #[test]
+    fn pages_without_english_are_missing_it() {
+        assert!(matches!(
+            Wiktionary.parse(
+                "perro",
+                "<div><h2><span class=\"mw-headline\" id=\"Spanish\">Spanish</span></h2></div>"
+            ),
+            Err(ScrapeError::MissingSelector("#English"))
+        ));
+    }
+}
@@ -0,0 +1,267 @@
+use std::{collections::HashSet, sync::RwLock};
+
+use lazy_static::lazy_static;
+
+use super::RESTRICTOR;
+
+/// How many "did you mean"s a miss gets.
+pub(crate) const MAX_SUGGESTIONS: usize = 5;
+
+/// How far off a suggestion can be spelled, in typos.
+const MAX_DISTANCE: f32 = 2.0;
+
+/// How much closer a word that sounds the same counts as, so "fonetik" can
+/// find "phonetic" despite being three typos away. Words that come down to a
+/// single consonant, like "the" and "rye", sound like too much to count.
+const SOUNDS_ALIKE: f32 = 1.5;
+
+/// Letters next to each other on a qwerty keyboard, each row sitting half a
+/// key to the right of the one above.
+const KEYBOARD: [&str; 3] = ["qwertyuiop", "asdfghjkl", "zxcvbnm"];
+
+/// The bundled word list, which is every word that comes up at least three
+/// times in the books in Peter Norvig's big.txt, most common first. It's
+/// built by `scripts/words.sh`, which leaves out Project Gutenberg's license.
+pub(super) const WORD_LIST: &str = include_str!("../../words.txt");
+
+lazy_static! {
+    static ref BUNDLED: Vec<Known> = WORD_LIST
+        .lines()
+        .filter(|word| !word.is_empty() && !RESTRICTOR.is_restricted(word))
+        .map(Known::new)
+        .collect();
+}
+
+/// A word that can be suggested.
+struct Known {
+    word: String,
+    lower: Vec<char>,
+    sound: String,
+}
+
+impl Known {
+    fn new(word: &str) -> Self {
+        let lower = word.to_lowercase();
+        Self {
+            word: word.to_string(),
+            sound: sound(&lower),
+            lower: lower.chars().collect(),
+        }
+    }
+}
+
+/// Words found by the sources.
+#[derive(Default)]
+struct Learned {
+    /// In the order they were found.
+    words: Vec<Known>,
+    /// The same words, for telling quickly whether one is new.
+    seen: HashSet<String>,
+}
+
+/// Suggests spellings for words nobody has, out of the bundled word list and
+/// every word that has been looked up successfully.
+#[derive(Default)]
+pub(crate) struct Speller {
+    learned: RwLock<Learned>,
+}
+
+impl Speller {
+    /// Remembers that `word` is a word, so it can be suggested.
+    pub(crate) fn learn(&self, word: &str) {
+        if word.is_empty() || RESTRICTOR.is_restricted(&word.to_lowercase()) {
+            return;
+        }
+        // most words are learned again every time they're looked up, which
+        // shouldn't hold up everyone asking for suggestions
+        if self.learned.read().unwrap().seen.contains(word) {
+            return;
+        }
+        let known = Known::new(word);
+        let mut learned = self.learned.write().unwrap();
+        if learned.seen.insert(word.to_string()) {
+            learned.words.push(known);
+        }
+    }
+
+    /// The words `word` was most likely meant to be, best first. Closer
+    /// spellings win, then words that sound the same, then common words.
+    pub(crate) fn suggest(&self, word: &str) -> Vec<String> {
+        let lower = word.to_lowercase();
+        let asked: Vec<char> = lower.chars().collect();
+        let asked_sound = sound(&lower);
+
+        let learned = self.learned.read().unwrap();
+        // learned words go after the bundled ones, so they rank as rarer
+        let mut scored: Vec<_> = BUNDLED
+            .iter()
+            .chain(learned.words.iter())
+            .enumerate()
+            .filter(|(_, known)| known.lower != asked)
+            .filter(|(_, known)| known.lower.len().abs_diff(asked.len()) <= 2)
+            .filter_map(|(rank, known)| {
+                let mut score = distance(&asked, &known.lower);
+                if asked_sound.len() > 1 && known.sound == asked_sound {
+                    score -= SOUNDS_ALIKE;
+                }
+                (score <= MAX_DISTANCE).then_some((score, rank, known))
+            })
+            .collect();
+        scored.sort_by(|(a, a_rank, _), (b, b_rank, _)| a.total_cmp(b).then(a_rank.cmp(b_rank)));
+
+        let mut seen = HashSet::new();
+        scored
+            .into_iter()
+            .filter(|(_, _, known)| seen.insert(&known.lower))
+            .take(MAX_SUGGESTIONS)
+            .map(|(_, _, known)| known.word.clone())
+            .collect()
+    }
+}
+
+/// The edit distance between `a` and `b`, counting a swap of two letters as
+/// one typo and hitting the key next to the right one as half of one.
+fn distance(a: &[char], b: &[char]) -> f32 {
+    // three rows are enough, since a swap only looks two rows back
+    let mut before = vec![0.0; b.len() + 1];
+    let mut previous: Vec<f32> = (0..=b.len()).map(|j| j as f32).collect();
+    let mut current = vec![0.0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        current[0] = i as f32;
+        for j in 1..=b.len() {
+            let substitution = if a[i - 1] == b[j - 1] {
+                0.0
+            } else if adjacent(a[i - 1], b[j - 1]) {
+                0.5
+            } else {
+                1.0
+            };
+            let mut best = (previous[j - 1] + substitution)
+                .min(previous[j] + 1.0)
+                .min(current[j - 1] + 1.0);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(before[j - 2] + 1.0);
+            }
+            current[j] = best;
+        }
+        std::mem::swap(&mut before, &mut previous);
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// Whether `a` and `b` are next to each other on the keyboard.
+fn adjacent(a: char, b: char) -> bool {
+    let position = |c| {
+        KEYBOARD
+            .iter()
+            .enumerate()
+            .find_map(|(row, keys)| Some((row, keys.find(c)?)))
+    };
+    let (Some((a_row, a_col)), Some((b_row, b_col))) = (position(a), position(b)) else {
+        return false;
+    };
+    match (a_row, b_row) {
+        _ if a_row == b_row => a_col.abs_diff(b_col) == 1,
+        // a key touches the one below it and the one below and to the left
+        _ if b_row == a_row + 1 => a_col == b_col || a_col == b_col + 1,
+        _ if a_row == b_row + 1 => b_col == a_col || b_col == a_col + 1,
+        _ => false,
+    }
+}
+
+/// A rough key for how `word` sounds, so words spelled the way they sound
+/// come out the same as the real spelling: "fonetik" and "phonetic" are
+/// both "fntk".
+fn sound(word: &str) -> String {
+    let word: String = word.chars().filter(char::is_ascii_alphabetic).collect();
+    // the "k" in "knife" and the "w" in "write" are silent
+    let word = match word.get(..2) {
+        Some("kn" | "wr") => word[1..].to_string(),
+        _ => word,
+    };
+    let word = word
+        .replace("ph", "f")
+        .replace("gh", "")
+        .replace("ck", "k")
+        .replace('x', "ks");
+
+    let chars: Vec<char> = word.chars().collect();
+    let mut key = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        let c = match c {
+            'c' if matches!(chars.get(i + 1), Some('e' | 'i' | 'y')) => 's',
+            'c' | 'q' => 'k',
+            'z' => 's',
+            // vowels are where misspellings mostly go wrong, so only the
+            // first letter keeps one
+            'a' | 'e' | 'i' | 'o' | 'u' | 'y' | 'h' | 'w' if i > 0 => continue,
+            c => c,
+        };
+        if !key.ends_with(c) {
+            key.push(c);
+        }
+    }
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typos_are_caught() {
+        let speller = Speller::default();
+        let first = |word| speller.suggest(word).into_iter().next();
+
+        // swapped letters and a wrong vowel
+        assert_eq!(first("dgo").as_deref(), Some("dog"));
+        assert_eq!(first("recieve").as_deref(), Some("receive"));
+        assert_eq!(first("definately").as_deref(), Some("definitely"));
+        // "r" is next to "t" on the keyboard
+        assert_eq!(first("rhe").as_deref(), Some("the"));
+
+        assert!(!speller.suggest("dog").contains(&"dog".to_string()));
+        // nothing from the books' license made it into the word list
+        assert!(!speller
+            .suggest("gutenburg")
+            .contains(&"gutenberg".to_string()));
+        assert!(!speller.suggest("ebok").contains(&"ebook".to_string()));
+        assert!(speller.suggest("qqqqqqqq").is_empty());
+        assert!(speller.suggest("dgo").len() <= MAX_SUGGESTIONS);
+    }
+
+    #[test]
+    fn learned_words_sound_alike() {
+        let speller = Speller::default();
+        assert!(!speller.suggest("fonetik").contains(&"phonetic".to_string()));
+
+        speller.learn("phonetic");
+        speller.learn("phonetic");
+        assert_eq!(speller.learned.read().unwrap().words.len(), 1);
+        assert_eq!(sound("fonetik"), sound("phonetic"));
+        assert_eq!(speller.suggest("fonetik")[0], "phonetic");
+    }
+
+    #[test]
+    fn restricted_words_are_never_suggested() {
+        let speller = Speller::default();
+        speller.learn("nakedness");
+        assert!(!speller.suggest("nakd").contains(&"naked".to_string()));
+        assert!(!speller
+            .suggest("nakednes")
+            .contains(&"nakedness".to_string()));
+    }
+
+    #[test]
+    fn neighbouring_keys_are_closer() {
+        let chars = |word: &str| word.chars().collect::<Vec<_>>();
+        assert_eq!(distance(&chars("dog"), &chars("dog")), 0.0);
+        assert_eq!(distance(&chars("dog"), &chars("dgo")), 1.0);
+        assert_eq!(distance(&chars("dog"), &chars("fog")), 0.5);
+        assert_eq!(distance(&chars("dog"), &chars("log")), 1.0);
+        assert!(adjacent('a', 'z') && adjacent('s', 'z') && adjacent('q', 'a'));
+        assert!(!adjacent('a', 'x') && !adjacent('q', 'p'));
+    }
+}
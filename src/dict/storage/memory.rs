@@ -0,0 +1,48 @@
+use std::{collections::HashMap, sync::RwLock};
+
+use super::{Storage, StorageResult};
+
+/// Keeps everything in a map, so it's gone when the process exits.
+#[derive(Default)]
+pub(crate) struct MemoryStorage {
+    map: RwLock<HashMap<String, String>>,
+}
+
+#[rocket::async_trait]
+impl Storage for MemoryStorage {
+    async fn get(&self, key: &str) -> StorageResult<Option<String>> {
+        Ok(self.map.read().unwrap().get(key).cloned())
+    }
+
+    async fn set(&self, key: &str, value: &str) -> StorageResult<()> {
+        self.map
+            .write()
+            .unwrap()
+            .insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> StorageResult<()> {
+        self.map.write().unwrap().remove(key);
+        Ok(())
+    }
+
+    async fn incr(&self, key: &str, by: i64) -> StorageResult<i64> {
+        let mut map = self.map.write().unwrap();
+        let count = map.entry(key.to_string()).or_default();
+        let new = count.parse::<i64>().unwrap_or(0) + by;
+        *count = new.to_string();
+        Ok(new)
+    }
+
+    async fn scan(&self, prefix: &str) -> StorageResult<Vec<(String, String)>> {
+        Ok(self
+            .map
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(key, _)| key.starts_with(prefix))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect())
+    }
+}
@@ -0,0 +1,206 @@
+mod memory;
+mod redis;
+mod sled;
+
+use std::{fmt::Display, path::PathBuf, sync::Arc};
+
+use rocket::{fairing::AdHoc, Build, Rocket};
+use serde::Deserialize;
+
+pub(crate) use self::memory::MemoryStorage;
+pub(crate) use self::redis::RedisStorage;
+pub(crate) use self::sled::SledStorage;
+
+#[derive(Debug)]
+pub(crate) enum StorageError {
+    Sled(::sled::Error),
+    Redis(::redis::RedisError),
+    Corrupt(String),
+    /// The runtime shut down before the call could finish.
+    Cancelled,
+}
+
+impl Display for StorageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StorageError::Sled(err) => write!(f, "sled: {}", err),
+            StorageError::Redis(err) => write!(f, "redis: {}", err),
+            StorageError::Corrupt(key) => write!(f, "`{}` holds something unreadable", key),
+            StorageError::Cancelled => write!(f, "cancelled, since the server is shutting down"),
+        }
+    }
+}
+
+impl From<::sled::Error> for StorageError {
+    fn from(err: ::sled::Error) -> Self {
+        StorageError::Sled(err)
+    }
+}
+
+impl From<::redis::RedisError> for StorageError {
+    fn from(err: ::redis::RedisError) -> Self {
+        StorageError::Redis(err)
+    }
+}
+
+pub(crate) type StorageResult<T> = Result<T, StorageError>;
+
+/// A string key-value store that everything persistent is kept in.
+#[rocket::async_trait]
+pub(crate) trait Storage: Send + Sync {
+    async fn get(&self, key: &str) -> StorageResult<Option<String>>;
+
+    async fn set(&self, key: &str, value: &str) -> StorageResult<()>;
+
+    async fn delete(&self, key: &str) -> StorageResult<()>;
+
+    /// Adds `by` to the number stored at `key`, starting from 0, and
+    /// returns the new value.
+    async fn incr(&self, key: &str, by: i64) -> StorageResult<i64>;
+
+    /// Every key starting with `prefix`, along with its value.
+    async fn scan(&self, prefix: &str) -> StorageResult<Vec<(String, String)>>;
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Backend {
+    #[default]
+    Memory,
+    Sled,
+    Redis,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub(crate) struct StorageConfig {
+    pub(crate) backend: Backend,
+    /// The database directory, for sled.
+    pub(crate) path: Option<PathBuf>,
+    /// The server to connect to, for redis.
+    pub(crate) url: Option<String>,
+}
+
+pub(crate) async fn open(config: &StorageConfig) -> Result<Arc<dyn Storage>, String> {
+    Ok(match config.backend {
+        Backend::Memory => Arc::new(MemoryStorage::default()),
+        Backend::Sled => {
+            let path = config.path.as_ref().ok_or("sled storage needs a `path`")?;
+            Arc::new(SledStorage::open(path).map_err(|err| err.to_string())?)
+        }
+        Backend::Redis => {
+            let url = config.url.as_ref().ok_or("redis storage needs a `url`")?;
+            Arc::new(
+                RedisStorage::connect(url)
+                    .await
+                    .map_err(|err| err.to_string())?,
+            )
+        }
+    })
+}
+
+/// Opens the backend picked by the `storage` config and manages it as an
+/// `Arc<dyn Storage>`. This has to be attached before anything built on it.
+pub(crate) fn fairing() -> AdHoc {
+    AdHoc::try_on_ignite("Storage", |rocket: Rocket<Build>| async move {
+        let config: StorageConfig = match rocket.figment().extract_inner("storage") {
+            Ok(config) => config,
+            Err(err) if err.missing() => StorageConfig::default(),
+            Err(err) => {
+                error!("invalid storage config: {}", err);
+                return Err(rocket);
+            }
+        };
+
+        match open(&config).await {
+            Ok(storage) => Ok(rocket.manage(storage)),
+            Err(err) => {
+                error!("couldn't open storage: {}", err);
+                Err(rocket)
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rocket::async_test]
+    async fn set_then_get() {
+        let storage = MemoryStorage::default();
+
+        assert_eq!(storage.get("word:dog").await.unwrap(), None);
+
+        storage.set("word:dog", "woof").await.unwrap();
+        assert_eq!(
+            storage.get("word:dog").await.unwrap().as_deref(),
+            Some("woof")
+        );
+
+        storage.set("word:dog", "bark").await.unwrap();
+        assert_eq!(
+            storage.get("word:dog").await.unwrap().as_deref(),
+            Some("bark")
+        );
+    }
+
+    #[rocket::async_test]
+    async fn delete() {
+        let storage = MemoryStorage::default();
+
+        storage.set("word:cat", "meow").await.unwrap();
+        storage.delete("word:cat").await.unwrap();
+        assert_eq!(storage.get("word:cat").await.unwrap(), None);
+
+        // deleting something that isn't there is fine
+        storage.delete("word:cat").await.unwrap();
+    }
+
+    #[rocket::async_test]
+    async fn incr() {
+        let storage = MemoryStorage::default();
+
+        assert_eq!(storage.incr("lookups:dog", 1).await.unwrap(), 1);
+        assert_eq!(storage.incr("lookups:dog", 2).await.unwrap(), 3);
+        assert_eq!(
+            storage.get("lookups:dog").await.unwrap().as_deref(),
+            Some("3")
+        );
+    }
+
+    #[rocket::async_test]
+    async fn scan() {
+        let storage = MemoryStorage::default();
+
+        storage.set("lookups:dog", "3").await.unwrap();
+        storage.set("lookups:cat", "1").await.unwrap();
+        storage.set("word:dog", "{}").await.unwrap();
+
+        let mut found = storage.scan("lookups:").await.unwrap();
+        found.sort();
+        assert_eq!(
+            found,
+            [
+                ("lookups:cat".to_string(), "1".to_string()),
+                ("lookups:dog".to_string(), "3".to_string()),
+            ]
+        );
+    }
+
+    #[rocket::async_test]
+    async fn sled_runs_off_the_runtime() {
+        let storage = SledStorage::temporary().unwrap();
+
+        storage.set("word:dog", "woof").await.unwrap();
+        assert_eq!(
+            storage.get("word:dog").await.unwrap().as_deref(),
+            Some("woof")
+        );
+        assert_eq!(storage.incr("lookups:dog", 2).await.unwrap(), 2);
+        assert_eq!(storage.scan("lookups:").await.unwrap().len(), 1);
+
+        storage.delete("word:dog").await.unwrap();
+        assert_eq!(storage.get("word:dog").await.unwrap(), None);
+    }
+}
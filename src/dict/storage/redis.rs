@@ -0,0 +1,68 @@
+use redis::{aio::ConnectionManager, AsyncCommands, Client};
+use rocket::futures::StreamExt;
+
+use super::{Storage, StorageResult};
+
+/// A redis server, shared between every instance of the site.
+pub(crate) struct RedisStorage {
+    conn: ConnectionManager,
+}
+
+impl RedisStorage {
+    pub(crate) async fn connect(url: &str) -> StorageResult<Self> {
+        let client = Client::open(url)?;
+        Ok(Self {
+            conn: ConnectionManager::new(client).await?,
+        })
+    }
+}
+
+#[rocket::async_trait]
+impl Storage for RedisStorage {
+    async fn get(&self, key: &str) -> StorageResult<Option<String>> {
+        Ok(self.conn.clone().get(key).await?)
+    }
+
+    async fn set(&self, key: &str, value: &str) -> StorageResult<()> {
+        Ok(self.conn.clone().set(key, value).await?)
+    }
+
+    async fn delete(&self, key: &str) -> StorageResult<()> {
+        Ok(self.conn.clone().del(key).await?)
+    }
+
+    async fn incr(&self, key: &str, by: i64) -> StorageResult<i64> {
+        Ok(self.conn.clone().incr(key, by).await?)
+    }
+
+    async fn scan(&self, prefix: &str) -> StorageResult<Vec<(String, String)>> {
+        let mut conn = self.conn.clone();
+
+        let pattern = format!("{}*", escape_glob(prefix));
+        let keys: Vec<String> = conn.scan_match(pattern).await?.collect().await;
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // keys can disappear between the scan and the get
+        let values: Vec<Option<String>> =
+            redis::cmd("MGET").arg(&keys).query_async(&mut conn).await?;
+        Ok(keys
+            .into_iter()
+            .zip(values)
+            .filter_map(|(key, value)| Some((key, value?)))
+            .collect())
+    }
+}
+
+/// Makes `text` match itself literally in a redis `MATCH` pattern.
+fn escape_glob(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
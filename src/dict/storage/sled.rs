@@ -0,0 +1,108 @@
+use std::path::Path;
+
+use super::{Storage, StorageError, StorageResult};
+
+/// An embedded database in a local directory.
+pub(crate) struct SledStorage {
+    db: sled::Db,
+}
+
+impl SledStorage {
+    pub(crate) fn open(path: &Path) -> StorageResult<Self> {
+        Ok(Self {
+            db: sled::open(path)?,
+        })
+    }
+
+    /// A database that's thrown away once it's dropped.
+    #[cfg(test)]
+    pub(crate) fn temporary() -> StorageResult<Self> {
+        Ok(Self {
+            db: sled::Config::new().temporary(true).open()?,
+        })
+    }
+
+    /// Runs `f` on tokio's blocking threads, since sled calls can wait on
+    /// the disk and would otherwise hold up everything else on the runtime.
+    async fn blocking<T, F>(&self, f: F) -> StorageResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&sled::Db) -> StorageResult<T> + Send + 'static,
+    {
+        let db = self.db.clone();
+        match tokio::task::spawn_blocking(move || f(&db)).await {
+            Ok(result) => result,
+            Err(err) if err.is_panic() => std::panic::resume_unwind(err.into_panic()),
+            // the runtime is shutting down
+            Err(_) => Err(StorageError::Cancelled),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl Storage for SledStorage {
+    async fn get(&self, key: &str) -> StorageResult<Option<String>> {
+        let key = key.to_string();
+        self.blocking(move |db| match db.get(&key)? {
+            Some(bytes) => String::from_utf8(bytes.to_vec())
+                .map(Some)
+                .map_err(|_| StorageError::Corrupt(key)),
+            None => Ok(None),
+        })
+        .await
+    }
+
+    async fn set(&self, key: &str, value: &str) -> StorageResult<()> {
+        let (key, value) = (key.to_string(), value.to_string());
+        self.blocking(move |db| {
+            db.insert(key, value.as_bytes())?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn delete(&self, key: &str) -> StorageResult<()> {
+        let key = key.to_string();
+        self.blocking(move |db| {
+            db.remove(key)?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn incr(&self, key: &str, by: i64) -> StorageResult<i64> {
+        let key = key.to_string();
+        self.blocking(move |db| {
+            let new = db.update_and_fetch(&key, |old| {
+                let count = old
+                    .and_then(|bytes| std::str::from_utf8(bytes).ok()?.parse::<i64>().ok())
+                    .unwrap_or(0);
+                Some((count + by).to_string().into_bytes())
+            })?;
+
+            new.and_then(|bytes| std::str::from_utf8(&bytes).ok()?.parse().ok())
+                .ok_or(StorageError::Corrupt(key))
+        })
+        .await
+    }
+
+    async fn scan(&self, prefix: &str) -> StorageResult<Vec<(String, String)>> {
+        let prefix = prefix.to_string();
+        self.blocking(move |db| {
+            let mut found = Vec::new();
+            for pair in db.scan_prefix(&prefix) {
+                let (key, value) = pair?;
+                match (
+                    String::from_utf8(key.to_vec()),
+                    String::from_utf8(value.to_vec()),
+                ) {
+                    (Ok(key), Ok(value)) => found.push((key, value)),
+                    (Ok(key), Err(_)) => return Err(StorageError::Corrupt(key)),
+                    (Err(_), _) => return Err(StorageError::Corrupt(prefix)),
+                }
+            }
+            Ok(found)
+        })
+        .await
+    }
+}
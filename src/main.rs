@@ -1,6 +1,7 @@
 #[macro_use]
 extern crate rocket;
 
+mod admin;
 mod dict;
 mod not_found;
 mod routes;
@@ -9,7 +10,35 @@ mod routes;
 fn rocket() -> _ {
     let _dotenv = dotenv::dotenv();
     rocket::build()
-        .mount("/", routes![routes::guantanamo_bay, routes::res])
+        .attach(dict::storage::fairing())
+        .attach(dict::http::Http::fairing())
+        .attach(dict::sources::SourceRegistry::fairing())
+        .attach(dict::WordCache::fairing())
+        .attach(dict::Canary::fairing())
+        .attach(dict::Lookups::fairing())
+        .attach(admin::fairing())
+        .mount(
+            "/",
+            routes![
+                routes::index,
+                routes::define,
+                routes::guantanamo_bay,
+                routes::res
+            ],
+        )
+        .mount(
+            "/api",
+            routes![
+                routes::api_define,
+                routes::api_define_stream,
+                routes::api_suggest,
+                routes::api_top,
+                routes::api_trending,
+                routes::api_health,
+                routes::api_canary,
+                routes::api_forget_misses
+            ],
+        )
         .register("/", catchers![not_found::general_not_found])
         .register("/api", catchers![not_found::api_not_found])
 }
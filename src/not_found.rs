@@ -1,11 +1,38 @@
 use askama::Template;
 use rand::Rng;
-use rocket::response::content::{RawJson, RawHtml};
+use rocket::{response::content::RawHtml, serde::json::Json, Request};
+use serde::Serialize;
+
+use crate::dict::{Query, WordCache};
 
 #[derive(Template)]
 #[template(path = "not_found.html")]
 struct NotFoundTemplate {
     emoji: String,
+    /// Words the last part of the path might have been meant to be.
+    suggestions: Vec<String>,
+}
+
+/// The body of every 404 from the `/api` routes, so the client can tell
+/// an unknown word apart from a broken response.
+#[derive(Serialize)]
+pub(crate) struct ApiNotFound {
+    pub(crate) error: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) word: Option<String>,
+    /// What the word might have been meant to be, best first.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) suggestions: Vec<String>,
+}
+
+impl ApiNotFound {
+    pub(crate) fn word(word: String, suggestions: Vec<String>) -> Self {
+        Self {
+            error: "word not found",
+            word: Some(word),
+            suggestions,
+        }
+    }
 }
 
 const EMOJIS: &[&str] = &[
@@ -23,13 +50,33 @@ const EMOJIS: &[&str] = &[
 ];
 
 #[catch(404)]
-pub(crate) fn general_not_found() -> Option<RawHtml<String>> {
+pub(crate) fn general_not_found(req: &Request) -> Option<RawHtml<String>> {
     let mut rng = rand::thread_rng();
     let emoji = EMOJIS[rng.gen_range(0..EMOJIS.len())].to_string();
-    NotFoundTemplate { emoji }.render().ok().map(|text| RawHtml(text))
+
+    // someone typing "/dgo" or "/define/dgo/" probably wanted "dog", but
+    // "/favicon.ico" isn't worth going through the whole word list for
+    let segments: Vec<_> = req.uri().path().segments().collect();
+    let word = match segments[..] {
+        [word] | ["define", word] if !word.contains('.') => Query::parse(word).ok(),
+        _ => None,
+    };
+    let suggestions = match (word, req.rocket().state::<WordCache>()) {
+        (Some(word), Some(cache)) => cache.suggest(word.as_str()),
+        _ => Vec::new(),
+    };
+
+    NotFoundTemplate { emoji, suggestions }
+        .render()
+        .ok()
+        .map(RawHtml)
 }
 
 #[catch(404)]
-pub(crate) fn api_not_found() -> RawJson<&'static str> {
-    RawJson("{}")
+pub(crate) fn api_not_found() -> Json<ApiNotFound> {
+    Json(ApiNotFound {
+        error: "not found",
+        word: None,
+        suggestions: Vec::new(),
+    })
 }
@@ -1,27 +1,68 @@
-use std::{
-    path::Path,
-    time::{SystemTime, UNIX_EPOCH},
-};
+use std::{path::Path, sync::Arc};
 
 use askama::Template;
 use rocket::{
     fs::NamedFile,
-    response::content::{RawHtml, RawJson},
+    http::Status,
+    response::{
+        content::RawHtml,
+        status::{BadRequest, NotFound},
+        stream::{Event, EventStream},
+    },
+    serde::json::Json,
+    State,
 };
-use tokio::sync::OnceCell;
-
-use crate::dict::{Word, RESTRICTOR};
+use serde::Serialize;
 
-#[derive(Debug)]
-struct WordRanking {
-    name: String,
-    count: i32,
-}
+use crate::{
+    admin::Admin,
+    dict::{
+        sources::SourceRegistry, BreakerStatus, Canary, CanaryStatus, Following, Lookups, Query,
+        Rejection, TrendingWord, Window, Word, WordCache, WordRanking,
+    },
+    not_found::ApiNotFound,
+};
 
 #[derive(Template)]
 #[template(path = "guantanamo_bay.html")]
 struct GuantanamoBayTemplate<'a> {
-    words: &'a Vec<WordRanking>,
+    today: &'a [TrendingWord],
+    week: &'a [TrendingWord],
+    words: &'a [WordRanking],
+}
+
+/// How one source is doing, for `/api/health`.
+#[derive(Serialize)]
+pub(crate) struct SourceHealth {
+    id: &'static str,
+    name: &'static str,
+    breaker: BreakerStatus,
+}
+
+#[derive(Serialize)]
+pub(crate) struct Health {
+    sources: Vec<SourceHealth>,
+}
+
+/// The body of a 400 from `/api`, for a word that can't be looked up.
+#[derive(Serialize)]
+pub(crate) struct ApiInvalid {
+    error: &'static str,
+    reason: String,
+}
+
+fn invalid(rejection: Rejection) -> BadRequest<Json<ApiInvalid>> {
+    BadRequest(Json(ApiInvalid {
+        error: "invalid word",
+        reason: rejection.to_string(),
+    }))
+}
+
+/// Why `/api/define` didn't answer with a word.
+#[derive(Responder)]
+pub(crate) enum DefineError {
+    Invalid(BadRequest<Json<ApiInvalid>>),
+    NotFound(NotFound<Json<ApiNotFound>>),
 }
 
 #[derive(Template)]
@@ -34,39 +75,16 @@ struct DefineTemplate {
     word: String,
 }
 
-static TOP_WORDS: OnceCell<Vec<WordRanking>> = OnceCell::const_new();
-
-#[get("/")]
-pub(crate) async fn guantanamo_bay() -> Option<RawHtml<String>> {
-    /* let words = match TOP_WORDS.get() {
-        Some(words) => words,
-        None => {
-            let keys: Vec<String> = db.keys("lookups:*".to_string()).await.unwrap();
-            let lookups: Vec<String> = db.get(&keys).await.unwrap();
-
-            let mut words: Vec<WordRanking> = keys
-                .iter()
-                .zip(lookups.iter())
-                .map(|(key, lookups)| WordRanking {
-                    name: key.split_once(':').unwrap().1.to_string(),
-                    count: lookups.parse().unwrap(),
-                })
-                .filter(|word| !RESTRICTOR.is_restricted(&word.name))
-                .collect();
-            words.sort_by_key(|word| word.count);
-            words.reverse();
-
-            TOP_WORDS.set(words).unwrap();
-
-            TOP_WORDS.get().unwrap()
-        }
-    }; */
-    let words = Vec::new();
-
-    GuantanamoBayTemplate { words: &words }
-        .render()
-        .ok()
-        .map(RawHtml)
+#[get("/top")]
+pub(crate) async fn guantanamo_bay(lookups: &State<Lookups>) -> Option<RawHtml<String>> {
+    GuantanamoBayTemplate {
+        today: &lookups.trending_in(Window::Today),
+        week: &lookups.trending_in(Window::Week),
+        words: &lookups.top(),
+    }
+    .render()
+    .ok()
+    .map(RawHtml)
 }
 
 #[get("/")]
@@ -75,8 +93,150 @@ pub(crate) fn index() -> Option<RawHtml<String>> {
 }
 
 #[get("/define/<word>")]
-pub(crate) fn define(word: String) -> Option<RawHtml<String>> {
-    DefineTemplate { word }.render().ok().map(RawHtml)
+pub(crate) fn define(word: Result<Query, Rejection>) -> Result<Option<RawHtml<String>>, Status> {
+    let word = word.map_err(|_| Status::BadRequest)?.to_string();
+    Ok(DefineTemplate { word }.render().ok().map(RawHtml))
+}
+
+#[get("/define/<word>")]
+pub(crate) async fn api_define(
+    word: Result<Query, Rejection>,
+    cache: &State<WordCache>,
+    lookups: &State<Lookups>,
+) -> Result<Json<Word>, DefineError> {
+    let word = word.map_err(|rejection| DefineError::Invalid(invalid(rejection)))?;
+    match cache.lookup(word.as_str()).await {
+        Some(entry) => {
+            lookups.record(word.as_str()).await;
+            Ok(Json(entry))
+        }
+        None => Err(DefineError::NotFound(NotFound(Json(ApiNotFound::word(
+            word.to_string(),
+            cache.suggest(word.as_str()),
+        ))))),
+    }
+}
+
+/// Like `api_define`, but as server-sent events: a `source` event for each
+/// source as soon as it answers, then either `word`, with the same entry
+/// `api_define` would give, or `not_found` with the same suggestions.
+/// Cached words skip straight to `word`.
+#[get("/define/<word>/stream")]
+pub(crate) async fn api_define_stream(
+    word: Result<Query, Rejection>,
+    cache: &State<WordCache>,
+    lookups: &State<Lookups>,
+) -> Result<EventStream![], BadRequest<Json<ApiInvalid>>> {
+    let word = word.map_err(invalid)?.to_string();
+    let following = cache.follow(&word).await;
+    let cache = cache.inner().clone();
+    let lookups = lookups.inner().clone();
+
+    Ok(EventStream! {
+        let mut flight = match following {
+            Following::Cached(entry) => {
+                lookups.record(&word).await;
+                yield Event::json(&entry).event("word");
+                return;
+            }
+            Following::Scraping(flight) => flight,
+            Following::Missing => {
+                let suggestions = cache.suggest(&word);
+                yield Event::json(&ApiNotFound::word(word, suggestions)).event("not_found");
+                return;
+            }
+        };
+
+        let mut sent = 0;
+        loop {
+            let (reports, done) = {
+                let flight = flight.borrow_and_update();
+                let done = flight.done.then(|| flight.word.clone());
+                (flight.reports[sent..].to_vec(), done)
+            };
+            sent += reports.len();
+            for report in reports {
+                yield Event::json(&report).event("source");
+            }
+
+            match done {
+                Some(Some(entry)) => {
+                    lookups.record(&word).await;
+                    yield Event::json(&entry).event("word");
+                    break;
+                }
+                Some(None) => {
+                    let not_found = ApiNotFound::word(word.clone(), cache.suggest(&word));
+                    yield Event::json(&not_found).event("not_found");
+                    break;
+                }
+                None => {}
+            }
+
+            // the scrape went away without finishing, which shouldn't happen
+            if flight.changed().await.is_err() {
+                break;
+            }
+        }
+    })
+}
+
+/// Forgets that `word` wasn't found, so it's looked up everywhere again
+/// instead of waiting for the miss to expire.
+#[delete("/admin/misses/<word>")]
+pub(crate) async fn api_forget_misses(
+    word: Result<Query, Rejection>,
+    _admin: Admin,
+    cache: &State<WordCache>,
+) -> Result<Status, BadRequest<Json<ApiInvalid>>> {
+    let word = word.map_err(invalid)?;
+    if cache.forget_misses(word.as_str()).await {
+        info!("forgot that `{}` wasn't found", word);
+    }
+    Ok(Status::NoContent)
+}
+
+/// Completes what's been typed into the search box so far, the most looked
+/// up words first.
+#[get("/suggest?<q>")]
+pub(crate) fn api_suggest(q: Option<&str>, lookups: &State<Lookups>) -> Json<Vec<String>> {
+    Json(lookups.complete(q.unwrap_or_default().trim_start()))
+}
+
+#[get("/top")]
+pub(crate) fn api_top(lookups: &State<Lookups>) -> Json<Vec<WordRanking>> {
+    Json(lookups.top().to_vec())
+}
+
+#[get("/trending?<window>")]
+pub(crate) fn api_trending(
+    window: Option<Window>,
+    lookups: &State<Lookups>,
+) -> Json<Vec<TrendingWord>> {
+    Json(
+        lookups
+            .trending_in(window.unwrap_or(Window::Today))
+            .to_vec(),
+    )
+}
+
+#[get("/health")]
+pub(crate) fn api_health(sources: &State<Arc<SourceRegistry>>) -> Json<Health> {
+    Json(Health {
+        sources: sources
+            .iter()
+            .map(|source| SourceHealth {
+                id: source.id(),
+                name: source.name(),
+                breaker: sources.breaker(source.as_ref()).status(),
+            })
+            .collect(),
+    })
+}
+
+#[get("/canary")]
+pub(crate) async fn api_canary(canary: &State<Canary>) -> Json<Vec<CanaryStatus>> {
+    Json(canary.status().await)
 }
 
 #[get("/<file>")]